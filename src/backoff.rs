@@ -0,0 +1,107 @@
+use crate::config::ReconnectConfig;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub struct Backoff {
+    initial_delay: Duration,
+    max_delay: Duration,
+    max_attempts: Option<u32>,
+    jitter: f64,
+    attempts: u32,
+    random_unit: fn() -> f64,
+}
+
+impl Backoff {
+    pub fn new(config: &ReconnectConfig) -> Self {
+        Self {
+            initial_delay: Duration::from_millis(config.initial_delay.unwrap_or(500)),
+            max_delay: Duration::from_millis(config.max_delay.unwrap_or(30000)),
+            max_attempts: config.max_attempts,
+            jitter: config.jitter.unwrap_or(0.2).clamp(0.0, 1.0),
+            attempts: 0,
+            random_unit: Self::clock_random_unit,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.attempts = 0;
+    }
+
+    //Returns None once the configured number of attempts is used up
+    pub fn next_delay(&mut self) -> Option<Duration> {
+        if let Some(max_attempts) = self.max_attempts {
+            if self.attempts >= max_attempts {
+                return None;
+            }
+        }
+        let exponential_delay = self.initial_delay.saturating_mul(2u32.saturating_pow(self.attempts)).min(self.max_delay);
+        self.attempts += 1;
+        let jitter_factor = 1.0 + self.jitter * (2.0 * (self.random_unit)() - 1.0);
+        Some(exponential_delay.mul_f64(jitter_factor))
+    }
+
+    //Jitter only has to keep several instances from reconnecting in lockstep, so the clock is random enough
+    fn clock_random_unit() -> f64 {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.subsec_nanos()).unwrap_or(0);
+        (nanos % 1_000_000) as f64 / 1_000_000.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn backoff(initial_delay: u64, max_delay: u64, max_attempts: Option<u32>, jitter: f64, random_unit: fn() -> f64) -> Backoff {
+        let config = ReconnectConfig {
+            initial_delay: Some(initial_delay),
+            max_delay: Some(max_delay),
+            max_attempts,
+            jitter: Some(jitter),
+        };
+        Backoff { random_unit, ..Backoff::new(&config) }
+    }
+
+    fn millis(delay: Option<Duration>) -> Option<u128> {
+        delay.map(|delay| delay.as_millis())
+    }
+
+    #[test]
+    fn doubles_the_delay_up_to_the_cap() {
+        let mut backoff = backoff(500, 3000, None, 0.0, || 0.5);
+        let delays: Vec<Option<u128>> = (0..6).map(|_| millis(backoff.next_delay())).collect();
+        assert_eq!(delays, vec![Some(500), Some(1000), Some(2000), Some(3000), Some(3000), Some(3000)]);
+    }
+
+    #[test]
+    fn keeps_jitter_within_bounds() {
+        let mut lowest = backoff(1000, 30000, None, 0.2, || 0.0);
+        let mut highest = backoff(1000, 30000, None, 0.2, || 1.0);
+        assert_eq!(millis(lowest.next_delay()), Some(800));
+        assert_eq!(millis(highest.next_delay()), Some(1200));
+
+        let mut clock = backoff(1000, 30000, None, 0.2, Backoff::clock_random_unit);
+        for _ in 0..20 {
+            let delay = clock.next_delay().unwrap();
+            clock.reset();
+            assert!(delay >= Duration::from_millis(800) && delay <= Duration::from_millis(1200));
+        }
+    }
+
+    #[test]
+    fn starts_over_after_reset() {
+        let mut backoff = backoff(500, 30000, None, 0.0, || 0.5);
+        backoff.next_delay();
+        backoff.next_delay();
+        backoff.reset();
+        assert_eq!(millis(backoff.next_delay()), Some(500));
+    }
+
+    #[test]
+    fn gives_up_after_max_attempts() {
+        let mut backoff = backoff(500, 30000, Some(2), 0.0, || 0.5);
+        assert!(backoff.next_delay().is_some());
+        assert!(backoff.next_delay().is_some());
+        assert!(backoff.next_delay().is_none());
+        backoff.reset();
+        assert!(backoff.next_delay().is_some());
+    }
+}
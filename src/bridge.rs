@@ -0,0 +1,250 @@
+//Ties the midi devices to the feedback console: connecting, failing over and back, polling and shutting down
+use crate::backoff::Backoff;
+use crate::config::{Config, PageChange};
+use crate::ma_interface::endpoint::Endpoint;
+use crate::ma_interface::executor_state::ExecutorStateCache;
+use crate::ma_interface::watchdog::{ConnectionHealth, Watchdog};
+use crate::ma_interface::{LoginCredentials, MaInterface, Update};
+use crate::midi_controller::MidiController;
+use crate::update_queue::UpdateQueue;
+use std::error::Error;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+use tokio::sync::Mutex;
+
+//How long the midi devices may take to hand over their last updates on shutdown
+const SHUTDOWN_DRAIN_TIMEOUT: Duration = Duration::from_millis(1000);
+
+//Hands every update from the midi devices to the feedback console and all mirror consoles
+pub async fn distribute_updates(mut update_receiver: UnboundedReceiver<Update>, feedback_console_sender: UnboundedSender<Update>, mirror_senders: Vec<UnboundedSender<Update>>) {
+    while let Some(update) = update_receiver.recv().await {
+        for mirror_sender in &mirror_senders {
+            let _ = mirror_sender.send(update.clone());
+        }
+        if feedback_console_sender.send(update).is_err() {
+            break;
+        }
+    }
+}
+
+enum PollLoopExit {
+    ConnectionLost,
+    FailBack(usize, Box<MaInterface>),
+    Shutdown,
+}
+
+pub async fn main_loop(config: Arc<Config>, endpoints: Vec<Endpoint>, login_credentials: LoginCredentials, mut midi_controller: MidiController, update_receiver: UnboundedReceiver<Update>, mirror_senders: &[UnboundedSender<Update>], mut shutdown_receiver: UnboundedReceiver<()>) -> Result<(), Box<dyn Error>> {
+    let exec_value_receiver_mutex = Arc::new(Mutex::new(update_receiver));
+    let mut current_page_index = 0;
+    //Goto page buttons only light up on page changes, so the devices have to learn the page they start on
+    midi_controller.receive_update_from_ma(Update::PageUpdate(PageChange::Goto(current_page_index))).await;
+    let executor_states = ExecutorStateCache::new();
+    let mut backoff = Backoff::new(&config.reconnect.clone().unwrap_or_default());
+    let failover = config.failover.clone().unwrap_or_default();
+    let max_requests = config.max_requests.unwrap_or(10);
+    let mut active_console = 0;
+    let mut failed_consoles = 0;
+    let mut failback_interface: Option<Box<MaInterface>> = None;
+    loop {
+        let url = &endpoints[active_console].url;
+        let connect_result = match failback_interface.take() {
+            Some(ma) => Ok(*ma),
+            None => tokio::select! {
+                result = MaInterface::new(&endpoints[active_console], &login_credentials, max_requests) => result,
+                _ = shutdown_receiver.recv() => return shutdown(midi_controller, None, &exec_value_receiver_mutex).await,
+            },
+        };
+        let mut ma = match connect_result {
+            Ok(ma) => ma,
+            Err(e) if e.requires_reconnect() => {
+                midi_controller.receive_update_from_ma(Update::ConnectionUpdate(ConnectionHealth::Lost)).await;
+                println!("Connecting to console {} at {} failed: {}", active_console + 1, url, e);
+                active_console = (active_console + 1) % endpoints.len();
+                failed_consoles += 1;
+                //Backing off only once all consoles failed, so a backup is tried right away
+                if failed_consoles >= endpoints.len() {
+                    failed_consoles = 0;
+                    let Some(delay) = backoff.next_delay() else {
+                        return Err(e.into());
+                    };
+                    println!("No console reachable. Retrying in {} ms", delay.as_millis());
+                    tokio::select! {
+                        _ = tokio::time::sleep(delay) => {}
+                        _ = shutdown_receiver.recv() => return shutdown(midi_controller, None, &exec_value_receiver_mutex).await,
+                    }
+                }
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+        ma.set_page_index(current_page_index);
+        ma.set_follow_console_page(config.follow_console_page.unwrap_or(false));
+        ma.set_executor_ranges(&config.executor_ranges());
+        ma.set_executor_state_cache(executor_states.clone());
+        let ma_mutex = Arc::new(Mutex::new(ma));
+        println!("Console {} at {} is live", active_console + 1, url);
+        backoff.reset();
+        failed_consoles = 0;
+        midi_controller.receive_update_from_ma(Update::ConnectionUpdate(ConnectionHealth::Connected)).await;
+        midi_controller.receive_update_from_ma(Update::Resync).await;
+        let forward_task = tokio::spawn(update_to_ma_forward_loop(ma_mutex.clone(), exec_value_receiver_mutex.clone()));
+
+        let (health_sender, health_receiver) = tokio::sync::mpsc::unbounded_channel();
+        let watchdog = Watchdog::new(&config.watchdog.clone().unwrap_or_default(), ma_mutex.lock().await.last_received(), health_sender);
+        let watchdog_task = tokio::spawn(watchdog.run());
+
+        let (failback_sender, failback_receiver) = tokio::sync::mpsc::unbounded_channel();
+        let failback_task = if failover.failback.unwrap_or(false) && active_console > 0 {
+            let check_interval = Duration::from_millis(failover.failback_check_interval.unwrap_or(5000));
+            Some(tokio::spawn(failback_probe_loop(endpoints[..active_console].to_vec(), login_credentials.clone(), max_requests, check_interval, failback_sender)))
+        } else {
+            None
+        };
+
+        let exit = tokio::select! {
+            exit = ma_poll_loop(config.ma_poll_interval, ma_mutex.clone(), &mut midi_controller, health_receiver, failback_receiver, mirror_senders, &mut current_page_index) => exit,
+            _ = shutdown_receiver.recv() => PollLoopExit::Shutdown,
+        };
+        watchdog_task.abort();
+        forward_task.abort();
+        let _ = forward_task.await;
+        if let Some(failback_task) = failback_task {
+            failback_task.abort();
+        }
+        match exit {
+            PollLoopExit::ConnectionLost => {
+                midi_controller.receive_update_from_ma(Update::ConnectionUpdate(ConnectionHealth::Lost)).await;
+                active_console = (active_console + 1) % endpoints.len();
+                println!("Network fail. Trying to reconnect to console {}...", active_console + 1);
+            }
+            PollLoopExit::FailBack(console, ma) => {
+                println!("Console {} is reachable again. Failing back...", console + 1);
+                active_console = console;
+                failback_interface = Some(ma);
+            }
+            PollLoopExit::Shutdown => return shutdown(midi_controller, Some(ma_mutex), &exec_value_receiver_mutex).await,
+        }
+    }
+}
+
+//Midi input is stopped first, so the updates still pending can be drained completely before logging out
+async fn shutdown(midi_controller: MidiController, ma_mutex: Option<Arc<Mutex<MaInterface>>>, exec_value_receiver_mutex: &Mutex<UnboundedReceiver<Update>>) -> Result<(), Box<dyn Error>> {
+    println!("Shutting down...");
+    midi_controller.shutdown().await;
+    let mut exec_value_receiver = exec_value_receiver_mutex.lock().await;
+    let mut queue = UpdateQueue::new();
+    //The channel closes once the midi devices and their pending fader updates are gone
+    let _ = tokio::time::timeout(SHUTDOWN_DRAIN_TIMEOUT, async {
+        while let Some(update) = exec_value_receiver.recv().await {
+            queue.push(update);
+        }
+    })
+    .await;
+    if let Some(ma_mutex) = ma_mutex {
+        let mut ma = ma_mutex.lock().await;
+        while let Some(update) = queue.pop() {
+            if let Err(e) = ma.send_update(update).await {
+                println!("Failed to send update to MA2: {}", e);
+                break;
+            }
+        }
+        match ma.close().await {
+            Ok(()) => println!("Logged out of MA2"),
+            Err(e) => println!("Logging out of MA2 failed: {}", e),
+        }
+    }
+    Ok(())
+}
+
+//Connects to the preferred consoles in order until one of them answers
+async fn failback_probe_loop(endpoints: Vec<Endpoint>, login_credentials: LoginCredentials, max_requests: u32, check_interval: Duration, failback_sender: UnboundedSender<(usize, MaInterface)>) {
+    let mut interval = tokio::time::interval(check_interval);
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        for (console, endpoint) in endpoints.iter().enumerate() {
+            if let Ok(ma) = MaInterface::new(endpoint, &login_credentials, max_requests).await {
+                let _ = failback_sender.send((console, ma));
+                return;
+            }
+        }
+    }
+}
+
+async fn ma_poll_loop(poll_interval: u64, ma_mutex: Arc<Mutex<MaInterface>>, midi_controller: &mut MidiController, mut health_receiver: UnboundedReceiver<ConnectionHealth>, mut failback_receiver: UnboundedReceiver<(usize, MaInterface)>, mirror_senders: &[UnboundedSender<Update>], current_page_index: &mut u32) -> PollLoopExit {
+    let mut interval = tokio::time::interval(Duration::from_millis(poll_interval));
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            Some((console, ma)) = failback_receiver.recv() => {
+                return PollLoopExit::FailBack(console, Box::new(ma));
+            }
+            health = health_receiver.recv() => {
+                match health {
+                    Some(ConnectionHealth::Lost) | None => {
+                        println!("Connection to MA2 lost");
+                        return PollLoopExit::ConnectionLost;
+                    }
+                    Some(health) => {
+                        println!("Connection to MA2 is {:?}", health);
+                        midi_controller.receive_update_from_ma(Update::ConnectionUpdate(health)).await;
+                        continue;
+                    }
+                }
+            }
+        }
+
+        let mut ma_lock = ma_mutex.lock().await;
+        let result = ma_lock.poll_changes().await;
+        let page_index = ma_lock.page_index();
+        drop(ma_lock);
+        match result {
+            Ok(events) => {
+                if page_index != *current_page_index {
+                    *current_page_index = page_index;
+                    println!("Switched to executor page {}", page_index + 1);
+                    midi_controller.receive_update_from_ma(Update::PageUpdate(PageChange::Goto(page_index))).await;
+                    //Mirror consoles follow the page of the feedback console
+                    for mirror_sender in mirror_senders {
+                        let _ = mirror_sender.send(Update::PageUpdate(PageChange::Goto(page_index)));
+                    }
+                }
+                for update in events.iter().filter_map(|event| event.to_update()) {
+                    midi_controller.receive_update_from_ma(update).await;
+                }
+            }
+            Err(e) if e.requires_reconnect() => {
+                println!("Polling MA2 failed: {}", e);
+                return PollLoopExit::ConnectionLost;
+            }
+            Err(e) => {
+                println!("Polling MA2 failed: {}", e);
+            }
+        }
+    }
+}
+
+//Updates piling up while MA2 is busy are coalesced, so only the newest fader positions get sent
+async fn update_to_ma_forward_loop(ma: Arc<Mutex<MaInterface>>, exec_value_receiver_mutex: Arc<Mutex<UnboundedReceiver<Update>>>) {
+    let mut exec_value_receiver = exec_value_receiver_mutex.lock().await;
+    let mut queue = UpdateQueue::new();
+    loop {
+        if queue.is_empty() {
+            match exec_value_receiver.recv().await {
+                Some(update) => queue.push(update),
+                None => break,
+            }
+        }
+        let mut ma = ma.lock().await;
+        //Updates arriving while waiting for the lock are merged before sending, then the whole batch is sent at once
+        while let Ok(update) = exec_value_receiver.try_recv() {
+            queue.push(update);
+        }
+        while let Some(update) = queue.pop() {
+            if let Err(e) = ma.send_update(update).await {
+                println!("Failed to send update to MA2: {}", e);
+            }
+        }
+    }
+}
@@ -1,26 +1,56 @@
 use serde::{Deserialize, Serialize};
-use std::{error::Error, fs, fs::File, io::Write};
+use std::{collections::BTreeSet, error::Error, fs, fs::File, io::Write, time::Duration};
 
-#[derive(Serialize, Deserialize, Default)]
+#[derive(Serialize, Deserialize)]
 pub struct Config {
-    pub console_ip: String,
+    pub console_ip: Option<String>,
+    pub console_address: Option<ConsoleAddress>,
+    pub consoles: Option<Vec<ConsoleAddress>>,
+    pub failover: Option<FailoverConfig>,
+    pub mirror_consoles: Option<Vec<ConsoleAddress>>,
     pub console_username: String,
     pub console_password: String,
     pub midi_devices: Vec<MidiDeviceConfig>,
     pub ma_poll_interval: u64,
+    pub follow_console_page: Option<bool>,
+    pub ma_executor_ranges: Option<Vec<ExecutorRange>>,
+    pub reconnect: Option<ReconnectConfig>,
+    pub watchdog: Option<WatchdogConfig>,
+    pub max_requests: Option<u32>,
+    pub capture_file: Option<String>,
+    pub replay_file: Option<String>,
 }
 
-impl Config {
-    pub fn default() -> Config {
+impl Default for Config {
+    fn default() -> Config {
         Config {
-            console_ip: String::from("192.168.178.71"),
+            console_ip: None,
+            console_address: Some(ConsoleAddress {
+                host: String::from("192.168.178.71"),
+                port: None,
+                scheme: None,
+                path: None,
+                tls: None,
+            }),
+            consoles: None,
+            failover: None,
+            mirror_consoles: None,
             console_username: String::from("remote"),
             console_password: String::from("remote"),
             midi_devices: Vec::new(),
             ma_poll_interval: 10,
+            follow_console_page: None,
+            ma_executor_ranges: None,
+            reconnect: None,
+            watchdog: None,
+            max_requests: None,
+            capture_file: None,
+            replay_file: None,
         }
     }
+}
 
+impl Config {
     pub fn write_default_config_file(filename: &str) -> Result<(), Box<dyn Error>> {
         let default_config = Config::default();
         let serialized = serde_json::to_string(&default_config)?;
@@ -30,11 +60,158 @@ impl Config {
         Ok(())
     }
 
+    pub fn executor_ranges(&self) -> Vec<ExecutorRange> {
+        if let Some(ranges) = &self.ma_executor_ranges {
+            return ranges.clone();
+        }
+        let mut executor_indices = BTreeSet::new();
+        for device in &self.midi_devices {
+            executor_indices.extend(device.model.motor_faders.iter().map(|fader| fader.ma_executor_index as u32));
+            executor_indices.extend(device.model.buttons.iter().map(|button| button.ma_executor_index as u32));
+        }
+        let mut ranges: Vec<ExecutorRange> = Vec::new();
+        for executor_index in executor_indices {
+            match ranges.last_mut() {
+                Some(range) if range.start_index + range.items_count == executor_index => range.items_count += 1,
+                _ => ranges.push(ExecutorRange {
+                    start_index: executor_index,
+                    items_count: 1,
+                }),
+            }
+        }
+        ranges
+    }
+
+    //console_ip is still accepted as a plain host with optional port for older config files
+    pub fn console_address(&self) -> Option<ConsoleAddress> {
+        if let Some(console_address) = &self.console_address {
+            return Some(console_address.clone());
+        }
+        self.console_ip.as_ref().map(|console_ip| ConsoleAddress {
+            host: console_ip.clone(),
+            port: None,
+            scheme: None,
+            path: None,
+            tls: None,
+        })
+    }
+
+    //Consoles in order of preference. The first one is the primary console, the others are backups
+    pub fn console_addresses(&self) -> Vec<ConsoleAddress> {
+        match &self.consoles {
+            Some(consoles) if !consoles.is_empty() => consoles.clone(),
+            _ => self.console_address().into_iter().collect(),
+        }
+    }
+
     pub fn read_from_config(file_name: &str) -> Result<Config, Box<dyn Error>> {
         let content = fs::read_to_string(file_name)?;
         let config: Config = serde_json::from_str(&content)?;
         Ok(config)
     }
+
+    //Catches settings serde accepts but that can't work, so they are reported at startup
+    pub fn validate(&self) -> Result<(), Box<dyn Error>> {
+        self.watchdog.clone().unwrap_or_default().validate(Duration::from_millis(self.ma_poll_interval))?;
+        for device in &self.midi_devices {
+            for special_master in &device.model.special_masters {
+                if let SpecialMaster::SpeedMaster(0) | SpecialMaster::RateMaster(0) = special_master.master {
+                    return Err(format!("Special master index 0 of {} is invalid, speed and rate masters are counted from 1", device.midi_in_port_name).into());
+                }
+                let (master_min_value, master_max_value) = special_master.master_range();
+                if master_min_value > master_max_value {
+                    return Err(format!("Special master of {} has master_min_value {} above master_max_value {}", device.midi_in_port_name, master_min_value, master_max_value).into());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ConsoleAddress {
+    pub host: String,
+    pub port: Option<u16>,
+    pub scheme: Option<WebsocketScheme>,
+    pub path: Option<String>,
+    pub tls: Option<TlsConfig>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum WebsocketScheme {
+    Ws,
+    Wss,
+}
+
+//Only used with the Wss scheme. ca_certificate is the path of an additional PEM encoded root certificate
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct TlsConfig {
+    pub verify_certificate: Option<bool>,
+    pub verify_hostname: Option<bool>,
+    pub ca_certificate: Option<String>,
+}
+
+//With failback enabled, consoles preferred over the live one are probed every failback_check_interval milliseconds
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct FailoverConfig {
+    pub failback: Option<bool>,
+    pub failback_check_interval: Option<u64>,
+}
+
+//Delays are given in milliseconds, jitter as fraction of the delay
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct ReconnectConfig {
+    pub initial_delay: Option<u64>,
+    pub max_delay: Option<u64>,
+    pub max_attempts: Option<u32>,
+    pub jitter: Option<f64>,
+}
+
+//Maximum age in milliseconds of the last playbacks response and keep alive reply before the connection counts as degraded or lost
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct WatchdogConfig {
+    pub playbacks_degraded_after: Option<u64>,
+    pub playbacks_lost_after: Option<u64>,
+    pub keep_alive_degraded_after: Option<u64>,
+    pub keep_alive_lost_after: Option<u64>,
+}
+
+impl WatchdogConfig {
+    pub fn playbacks_degraded_after(&self) -> Duration {
+        Duration::from_millis(self.playbacks_degraded_after.unwrap_or(500))
+    }
+
+    pub fn playbacks_lost_after(&self) -> Duration {
+        Duration::from_millis(self.playbacks_lost_after.unwrap_or(2000))
+    }
+
+    pub fn keep_alive_degraded_after(&self) -> Duration {
+        Duration::from_millis(self.keep_alive_degraded_after.unwrap_or(6000))
+    }
+
+    pub fn keep_alive_lost_after(&self) -> Duration {
+        Duration::from_millis(self.keep_alive_lost_after.unwrap_or(10000))
+    }
+
+    //Each poll refreshes the playbacks age, so it has to be allowed to grow beyond the poll interval without counting as degraded
+    fn validate(&self, poll_interval: Duration) -> Result<(), String> {
+        if self.playbacks_degraded_after() <= poll_interval {
+            return Err(format!("watchdog playbacks_degraded_after ({} ms) has to be larger than ma_poll_interval ({} ms)", self.playbacks_degraded_after().as_millis(), poll_interval.as_millis()));
+        }
+        if self.playbacks_lost_after() <= self.playbacks_degraded_after() {
+            return Err(format!("watchdog playbacks_lost_after ({} ms) has to be larger than playbacks_degraded_after ({} ms)", self.playbacks_lost_after().as_millis(), self.playbacks_degraded_after().as_millis()));
+        }
+        if self.keep_alive_lost_after() <= self.keep_alive_degraded_after() {
+            return Err(format!("watchdog keep_alive_lost_after ({} ms) has to be larger than keep_alive_degraded_after ({} ms)", self.keep_alive_lost_after().as_millis(), self.keep_alive_degraded_after().as_millis()));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct ExecutorRange {
+    pub start_index: u32,
+    pub items_count: u32,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -48,7 +225,15 @@ pub struct MidiDeviceConfig {
 pub struct DeviceModelConfig {
     pub motor_faders: Vec<MotorFaderConfig>,
     pub rotary_encoders: Vec<RotaryEncoderConfig>,
-    pub buttons: Vec<ButtonConfig>
+    pub buttons: Vec<ButtonConfig>,
+    #[serde(default)]
+    pub page_buttons: Vec<PageButtonConfig>,
+    #[serde(default)]
+    pub command_buttons: Vec<CommandButtonConfig>,
+    #[serde(default)]
+    pub special_masters: Vec<SpecialMasterConfig>,
+    pub status_indicator: Option<StatusIndicatorConfig>,
+    pub goodbye: Option<GoodbyeConfig>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -57,26 +242,58 @@ pub struct RotaryEncoderConfig {
 }
 
 
-#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ButtonPosition {
     Top,
     Mid,
     Bottom
 }
 
+//Goto takes the 0-based page index like the WebRemote, so Goto(0) selects the page the console shows as page 1
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum PageChange {
+    Next,
+    Previous,
+    Goto(u32),
+}
+
+//Midi bytes and led values shared by every kind of button. They are flattened into the button configs,
+//so they are written next to the other button settings
 #[derive(Serialize, Deserialize, Clone)]
-pub struct ButtonConfig {
+pub struct ButtonMidiConfig {
     pub input_midi_byte_0: u8,
     pub input_midi_byte_1: u8,
     pub output_midi_byte_0: u8,
     pub output_midi_byte_1: u8,
     pub low_value: Option<u8>,
     pub high_value: Option<u8>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ButtonConfig {
+    #[serde(flatten)]
+    pub midi: ButtonMidiConfig,
     pub input_feedback: Option<bool>,
     pub ma_executor_index: u8,
     pub position: ButtonPosition
 }
 
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PageButtonConfig {
+    #[serde(flatten)]
+    pub midi: ButtonMidiConfig,
+    pub action: PageChange,
+}
+
+//Commands may contain the placeholders {page} (current executor page number) and {value} (midi value of the input)
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CommandButtonConfig {
+    #[serde(flatten)]
+    pub midi: ButtonMidiConfig,
+    pub press_command: String,
+    pub release_command: Option<String>,
+}
+
 
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -90,3 +307,157 @@ pub struct MotorFaderConfig {
     pub input_feedback: Option<bool>,
     pub ma_executor_index: u8,
 }
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum SpecialMaster {
+    GrandMaster,
+    SpeedMaster(u8),
+    RateMaster(u8),
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum ControlInputMode {
+    Absolute,
+    Relative,
+}
+
+//master_min_value and master_max_value are given in percent for grand and rate masters and in BPM for speed masters
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SpecialMasterConfig {
+    pub input_midi_byte_0: u8,
+    pub input_midi_byte_1: u8,
+    pub output_midi_byte_0: u8,
+    pub output_midi_byte_1: u8,
+    pub min_value: Option<u8>,
+    pub max_value: Option<u8>,
+    pub input_mode: Option<ControlInputMode>,
+    pub relative_step: Option<f32>,
+    pub master: SpecialMaster,
+    pub master_min_value: Option<f32>,
+    pub master_max_value: Option<f32>,
+}
+
+impl SpecialMasterConfig {
+    pub fn master_range(&self) -> (f32, f32) {
+        let (default_min, default_max) = match self.master {
+            SpecialMaster::GrandMaster => (0.0, 100.0),
+            SpecialMaster::SpeedMaster(_) => (0.0, 240.0),
+            SpecialMaster::RateMaster(_) => (0.0, 100.0),
+        };
+        (self.master_min_value.unwrap_or(default_min), self.master_max_value.unwrap_or(default_max))
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub enum StatusIndication {
+    BlinkLed {
+        output_midi_byte_0: u8,
+        output_midi_byte_1: u8,
+        on_value: Option<u8>,
+        off_value: Option<u8>,
+        period: Option<u64>,
+    },
+    DimLeds {
+        dim_value: u8,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct StatusIndicatorConfig {
+    pub indication: StatusIndication,
+    pub indicate_degraded: Option<bool>,
+}
+
+//Midi output sent to the device when midiMA shuts down
+#[derive(Serialize, Deserialize, Clone)]
+pub struct GoodbyeConfig {
+    pub faders_to_zero: Option<bool>,
+    pub leds_off: Option<bool>,
+    pub led_off_value: Option<u8>,
+    pub messages: Option<Vec<[u8; 3]>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_with(ma_poll_interval: u64, watchdog: WatchdogConfig) -> Config {
+        Config {
+            ma_poll_interval,
+            watchdog: Some(watchdog),
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn accepts_default_config() {
+        assert!(Config::default().validate().is_ok());
+    }
+
+    #[test]
+    fn rejects_degraded_threshold_within_poll_interval() {
+        assert!(config_with(500, WatchdogConfig::default()).validate().is_err());
+        assert!(config_with(499, WatchdogConfig::default()).validate().is_ok());
+    }
+
+    #[test]
+    fn rejects_lost_threshold_below_degraded_threshold() {
+        let playbacks = WatchdogConfig {
+            playbacks_degraded_after: Some(1000),
+            playbacks_lost_after: Some(1000),
+            ..WatchdogConfig::default()
+        };
+        assert!(config_with(10, playbacks).validate().is_err());
+        let keep_alive = WatchdogConfig {
+            keep_alive_degraded_after: Some(12000),
+            ..WatchdogConfig::default()
+        };
+        assert!(config_with(10, keep_alive).validate().is_err());
+    }
+
+    #[test]
+    fn reads_button_midi_settings_next_to_the_button_settings() {
+        let json = r#"{"input_midi_byte_0":144,"input_midi_byte_1":16,"output_midi_byte_0":144,"output_midi_byte_1":17,"low_value":null,"high_value":100,"press_command":"Go","release_command":null}"#;
+        let config: CommandButtonConfig = serde_json::from_str(json).unwrap();
+        assert_eq!(config.midi.input_midi_byte_1, 16);
+        assert_eq!(config.midi.output_midi_byte_1, 17);
+        assert_eq!(config.midi.high_value, Some(100));
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&serde_json::to_string(&config).unwrap()).unwrap(), serde_json::from_str::<serde_json::Value>(json).unwrap());
+    }
+
+    #[test]
+    fn rejects_inverted_special_master_range() {
+        let special_master = SpecialMasterConfig {
+            input_midi_byte_0: 0xB0,
+            input_midi_byte_1: 0x10,
+            output_midi_byte_0: 0xB0,
+            output_midi_byte_1: 0x10,
+            min_value: None,
+            max_value: None,
+            input_mode: None,
+            relative_step: None,
+            master: SpecialMaster::SpeedMaster(1),
+            master_min_value: Some(300.0),
+            master_max_value: None,
+        };
+        let config_with_master = |special_master: SpecialMasterConfig| Config {
+            midi_devices: vec![MidiDeviceConfig {
+                midi_in_port_name: String::from("in"),
+                midi_out_port_name: String::from("out"),
+                model: DeviceModelConfig {
+                    motor_faders: Vec::new(),
+                    rotary_encoders: Vec::new(),
+                    buttons: Vec::new(),
+                    page_buttons: Vec::new(),
+                    command_buttons: Vec::new(),
+                    special_masters: vec![special_master],
+                    status_indicator: None,
+                    goodbye: None,
+                },
+            }],
+            ..Config::default()
+        };
+        assert!(config_with_master(special_master.clone()).validate().is_err());
+        assert!(config_with_master(SpecialMasterConfig { master_min_value: Some(240.0), ..special_master }).validate().is_ok());
+    }
+}
@@ -0,0 +1,10 @@
+pub mod backoff;
+pub mod bridge;
+pub mod config;
+pub mod ma_interface;
+pub mod midi_controller;
+pub mod mirror;
+pub mod periodic_update_sender;
+pub mod update_queue;
+
+pub use ma_interface::{FaderValue, LoginCredentials, Update};
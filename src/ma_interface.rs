@@ -1,43 +1,98 @@
 mod connection;
+mod decoder;
+pub mod endpoint;
+pub mod error;
+pub mod executor_state;
 pub mod objects;
+pub mod playbacks;
+pub mod playbacks_diff;
+pub mod recording;
 mod requests;
 pub mod responses;
+mod scheduler;
+pub mod watchdog;
 
-use crate::ma_interface::requests::{LoginRequest, PlaybacksRequest, FaderInputRequest, SessionIdRequest, ButtonInputRequest};
-use crate::ma_interface::responses::{LoginRequestResponse, SessionIdResponse};
+use crate::ma_interface::requests::{CloseRequest, LoginRequest, PlaybacksRequest, FaderInputRequest, SessionIdRequest, ButtonInputRequest, CommandRequest};
+use crate::ma_interface::responses::{CommandResponse, LoginRequestResponse, SessionIdResponse};
 use connection::Connection;
-use futures_util::StreamExt;
-use requests::RequestType;
-use responses::ResponseWithExplicitType;
-use serde::Serialize;
-use std::error::Error;
-use std::str::FromStr;
+use decoder::{decode_frame, DecodedFrame};
+use endpoint::Endpoint;
+use error::MaError;
+use executor_state::ExecutorStateCache;
+use playbacks::ExecutorRecord;
+use playbacks_diff::{ExecutorEvent, PlaybacksDiff};
+use requests::{Request, RequestType};
+use scheduler::{CoalesceKey, RequestScheduler, ResponseKind, ScheduledRequest};
 use std::time::Duration;
-use tokio::io::AsyncWriteExt;
 use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
 use tokio::task::JoinHandle;
-use tokio::time::interval;
+use tokio::time::{interval, Instant};
 use tokio_tungstenite::tungstenite::protocol::Message;
-use url::Url;
-use crate::config::ButtonPosition;
+use watchdog::{ConnectionHealth, LastReceived};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use crate::config::{ButtonPosition, ExecutorRange, PageChange, SpecialMaster};
 
 use self::responses::PlaybacksResponse;
 
+//Largest number of executors requested in a single playbacks request
+const MAX_PLAYBACKS_ITEMS_COUNT: u32 = 15;
+const RESPONSE_TIMEOUT: Duration = Duration::from_millis(2000);
+const CLOSE_TIMEOUT: Duration = Duration::from_millis(1000);
+
+#[derive(Clone)]
 pub struct LoginCredentials {
     pub username: String,
     pub password_hash: String,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
+#[allow(clippy::enum_variant_names)]
 pub enum Update {
     FaderUpdate(FaderValue),
     ButtonUpdate(ButtonValue),
+    PageUpdate(PageChange),
+    CommandUpdate(String),
+    SpecialMasterUpdate(SpecialMasterValue),
+    Resync,
+    ConnectionUpdate(ConnectionHealth),
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct FaderValue {
     pub fader_value: f32,
     pub exec_index: u8,
+    pub range: Option<FaderRange>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct FaderRange {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl Default for FaderRange {
+    fn default() -> Self {
+        Self { min: 0.0, max: 1.0 }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct SpecialMasterValue {
+    pub master: SpecialMaster,
+    pub value: f32,
+}
+
+impl SpecialMasterValue {
+    //MA2 numbers special masters as group.index, group 2 holds the grand master, groups 3 and 4 the speed and rate masters
+    pub fn command(&self) -> String {
+        let target = match self.master {
+            SpecialMaster::GrandMaster => String::from("2.1"),
+            SpecialMaster::SpeedMaster(index) => format!("3.{}", index),
+            SpecialMaster::RateMaster(index) => format!("4.{}", index),
+        };
+        format!("SpecialMaster {} At {:.1}", target, self.value)
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -51,96 +106,190 @@ struct ResponseSenders {
     pub playbacks: UnboundedSender<PlaybacksResponse>,
     pub session_id: UnboundedSender<SessionIdResponse>,
     pub login: UnboundedSender<LoginRequestResponse>,
+    pub command: UnboundedSender<CommandResponse>,
+    pub close: UnboundedSender<()>,
 }
 
 struct ResponseReceivers {
     pub playbacks: UnboundedReceiver<PlaybacksResponse>,
     pub session_id: UnboundedReceiver<SessionIdResponse>,
     pub login: UnboundedReceiver<LoginRequestResponse>,
+    pub command: UnboundedReceiver<CommandResponse>,
+    pub close: UnboundedReceiver<()>,
 }
 
 fn create_response_receiver_sender_pair() -> (ResponseSenders, ResponseReceivers) {
     let (playbacks_tx, playbacks_rx) = tokio::sync::mpsc::unbounded_channel();
     let (session_id_tx, session_id_rx) = tokio::sync::mpsc::unbounded_channel();
     let (login_tx, login_rx) = tokio::sync::mpsc::unbounded_channel();
+    let (command_tx, command_rx) = tokio::sync::mpsc::unbounded_channel();
+    let (close_tx, close_rx) = tokio::sync::mpsc::unbounded_channel();
     (
         ResponseSenders {
             playbacks: playbacks_tx,
             session_id: session_id_tx,
             login: login_tx,
+            command: command_tx,
+            close: close_tx,
         },
         ResponseReceivers {
             playbacks: playbacks_rx,
             session_id: session_id_rx,
             login: login_rx,
+            command: command_rx,
+            close: close_rx,
         },
     )
 }
 
 pub struct MaInterface {
     receiver_thread: JoinHandle<()>,
+    last_received: Arc<Mutex<LastReceived>>,
+    undecodable_frames: Arc<Mutex<u64>>,
     keep_alive_thread: JoinHandle<()>,
-    websocket_sender: UnboundedSender<Message>,
+    scheduler_thread: JoinHandle<()>,
+    websocket_sender: UnboundedSender<ScheduledRequest>,
     response_receivers: ResponseReceivers,
+    login_credentials: LoginCredentials,
+    max_requests: u32,
     session_id: i32,
+    page_index: u32,
+    console_page_index: Option<u32>,
+    follow_console_page: bool,
+    poll_ranges: Vec<ExecutorRange>,
+    executor_states: ExecutorStateCache,
+    playbacks_diff: PlaybacksDiff,
 }
 
 impl MaInterface {
-    pub async fn new(url: &Url, login_credentials: &LoginCredentials) -> Result<MaInterface, Box<dyn Error>> {
-        let connection = Connection::new(url).await?;
+    pub async fn new(endpoint: &Endpoint, login_credentials: &LoginCredentials, max_requests: u32) -> Result<MaInterface, MaError> {
+        let connection = Connection::new(endpoint).await?;
 
-        let keep_alive_tx = connection.tx.clone();
-        let websocket_sender = connection.tx.clone();
+        let (websocket_sender, scheduled_requests) = tokio::sync::mpsc::unbounded_channel();
+        let (received_tx, received_rx) = tokio::sync::mpsc::unbounded_channel();
+        let scheduler = RequestScheduler::new(max_requests, connection.tx.clone(), scheduled_requests, received_rx);
+        let scheduler_thread = tokio::spawn(scheduler.run());
+        let keep_alive_tx = websocket_sender.clone();
 
         let (response_senders, mut response_receivers) = create_response_receiver_sender_pair();
 
-        let receiver_thread = tokio::spawn(MaInterface::receive_loop(connection, response_senders));
-        let session_id = MaInterface::get_session_id(&websocket_sender, &mut response_receivers).await?;
+        let last_received = Arc::new(Mutex::new(LastReceived::new()));
+        let undecodable_frames = Arc::new(Mutex::new(0));
+        let receiver_thread = tokio::spawn(MaInterface::receive_loop(connection, response_senders, received_tx, last_received.clone(), undecodable_frames.clone()));
+        let session_id = MaInterface::get_session_id(&websocket_sender, &mut response_receivers, None).await?;
+        MaInterface::login(&websocket_sender, &mut response_receivers, login_credentials, &session_id, max_requests).await?;
         let keep_alive_thread = tokio::spawn(MaInterface::keep_alive_loop(keep_alive_tx, session_id));
-
-        MaInterface::login(&websocket_sender, &mut response_receivers, login_credentials, &session_id).await?;
         let interface = MaInterface {
             receiver_thread,
+            last_received,
+            undecodable_frames,
             keep_alive_thread,
+            scheduler_thread,
             websocket_sender,
             response_receivers,
+            login_credentials: login_credentials.clone(),
+            max_requests,
             session_id,
+            page_index: 0,
+            console_page_index: None,
+            follow_console_page: false,
+            poll_ranges: Vec::from([ExecutorRange { start_index: 0, items_count: 10 }]),
+            executor_states: ExecutorStateCache::new(),
+            playbacks_diff: PlaybacksDiff::new(),
         };
         Ok(interface)
     }
 
-    pub async fn poll_fader_values(&mut self) -> Result<Vec<f32>, Box<dyn Error>> {
-        let request = PlaybacksRequest {
-            request_type: RequestType::Playbacks.to_string(),
-            start_index: Vec::from([000]),
-            items_count: Vec::from([10]),
-            page_index: 0,
-            items_type: Vec::from([2]),
-            view: 2,
-            exec_button_view_mode: 2,
-            buttons_view_mode: 0,
-            session: self.session_id,
-        };
-        self.send_request(request)?;
-        let next = self.response_receivers.playbacks.recv().await;
-        if let Some(response) = next {
-            let mut v: Vec<f32> = Vec::new();
-            for group in response.itemGroups {
-                for group_of_five in group.items {
-                    for executor in group_of_five {
-                        for executor_block in executor.executor_blocks {
-                            v.push(executor_block.fader.value);
-                        }
-                    }
+    pub fn last_received(&self) -> Arc<Mutex<LastReceived>> {
+        self.last_received.clone()
+    }
+
+    //Number of frames received from the console that couldn't be decoded
+    pub async fn undecodable_frames(&self) -> u64 {
+        *self.undecodable_frames.lock().await
+    }
+
+    pub fn page_index(&self) -> u32 {
+        self.page_index
+    }
+
+    pub fn set_page_index(&mut self, page_index: u32) {
+        self.page_index = page_index;
+    }
+
+    pub fn set_follow_console_page(&mut self, follow_console_page: bool) {
+        self.follow_console_page = follow_console_page;
+    }
+
+    pub fn set_executor_ranges(&mut self, ranges: &[ExecutorRange]) {
+        //Polling is also what keeps the connection supervised, so at least one executor is always requested
+        let default_ranges = [ExecutorRange { start_index: 0, items_count: 1 }];
+        let ranges = if ranges.is_empty() { &default_ranges[..] } else { ranges };
+        self.poll_ranges = ranges
+            .iter()
+            .flat_map(|range| {
+                (range.start_index..range.start_index + range.items_count).step_by(MAX_PLAYBACKS_ITEMS_COUNT as usize).map(move |start_index| ExecutorRange {
+                    start_index,
+                    items_count: MAX_PLAYBACKS_ITEMS_COUNT.min(range.start_index + range.items_count - start_index),
+                })
+            })
+            .collect();
+    }
+
+    pub fn executor_states(&self) -> ExecutorStateCache {
+        self.executor_states.clone()
+    }
+
+    pub fn set_executor_state_cache(&mut self, executor_states: ExecutorStateCache) {
+        self.executor_states = executor_states;
+    }
+
+    pub async fn poll_executors(&mut self) -> Result<Vec<ExecutorRecord>, MaError> {
+        if self.session_invalidated() {
+            self.restore_session().await?;
+        }
+        self.report_command_responses();
+        let page_index = self.page_index;
+        for range in &self.poll_ranges {
+            let request = PlaybacksRequest {
+                request_type: RequestType::Playbacks.to_string(),
+                start_index: Vec::from([range.start_index]),
+                items_count: Vec::from([range.items_count]),
+                page_index,
+                items_type: Vec::from([2]),
+                view: 2,
+                exec_button_view_mode: 2,
+                buttons_view_mode: 0,
+                session: self.session_id,
+            };
+            self.send_request(request)?;
+        }
+        let mut records = Vec::new();
+        for _ in 0..self.poll_ranges.len() {
+            let next = tokio::time::timeout(RESPONSE_TIMEOUT, self.response_receivers.playbacks.recv()).await;
+            match next {
+                Ok(Some(response)) => {
+                    self.receive_console_page_index(response.iPage);
+                    records.extend(ExecutorRecord::from_response(page_index, &response));
                 }
+                Ok(None) => return Err(MaError::ChannelClosed),
+                Err(_) => return Err(MaError::Timeout),
             }
-            Ok(v)
-        } else {
-            Err("get_fader_values EOS".into())
         }
+        self.executor_states.update(&records).await;
+        Ok(records)
+    }
+
+    pub async fn poll_changes(&mut self) -> Result<Vec<ExecutorEvent>, MaError> {
+        let records = self.poll_executors().await?;
+        Ok(self.playbacks_diff.diff(records))
     }
 
-    pub fn send_update(&mut self, update: Update) -> Result<(), Box<dyn Error>> {
+    pub fn reset_changes(&mut self) {
+        self.playbacks_diff.reset();
+    }
+
+    pub async fn send_update(&mut self, update: Update) -> Result<(), MaError> {
         match update {
             Update::FaderUpdate(fader_value) => {
                 self.send_fader_value(&fader_value)?;
@@ -148,29 +297,144 @@ impl MaInterface {
             Update::ButtonUpdate(button_value) => {
                 self.send_button_value(&button_value)?;
             }
+            Update::PageUpdate(page_change) => {
+                self.change_page(page_change);
+            }
+            Update::CommandUpdate(command) => {
+                self.send_unconfirmed_command(&command)?;
+            }
+            Update::SpecialMasterUpdate(special_master_value) => {
+                let request = CommandRequest::new(self.session_id, &special_master_value.command());
+                self.send_coalesced_request(CoalesceKey::SpecialMaster(special_master_value.master), request)?;
+            }
+            Update::Resync | Update::ConnectionUpdate(_) => {}
         }
         Ok(())
     }
 
-    fn send_fader_value(&mut self, fader_value: &FaderValue) -> Result<(), Box<dyn Error>> {
-        let request = FaderInputRequest::new(self.session_id, fader_value.exec_index, 0, fader_value.fader_value);
+    //Waits for the console to answer, for tooling that needs the prompt. Midi input uses send_update instead
+    pub async fn send_command(&mut self, command: &str) -> Result<CommandResponse, MaError> {
+        self.report_command_responses();
+        let request = CommandRequest::new(self.session_id, command);
         self.send_request(request)?;
+        let next = tokio::time::timeout(RESPONSE_TIMEOUT, self.response_receivers.command.recv()).await;
+        match next {
+            Ok(Some(response)) => {
+                if let Some(error_message) = response.error_message() {
+                    Err(MaError::CommandRejected(error_message.to_string()))
+                } else {
+                    Ok(response)
+                }
+            }
+            Ok(None) => Err(MaError::ChannelClosed),
+            Err(_) => Err(MaError::Timeout),
+        }
+    }
+
+    //Doesn't wait for the answer, so a slow console can't hold up faders and buttons. Rejections are reported with the next poll
+    fn send_unconfirmed_command(&mut self, command: &str) -> Result<(), MaError> {
+        self.send_request(CommandRequest::new(self.session_id, command))
+    }
+
+    fn report_command_responses(&mut self) {
+        while let Ok(response) = self.response_receivers.command.try_recv() {
+            if let Some(error_message) = response.error_message() {
+                println!("MA2 rejected a command: {}", error_message);
+            }
+        }
+    }
+
+    //Logs out, so the session doesn't linger on the console until it times out
+    pub async fn close(&mut self) -> Result<(), MaError> {
+        self.keep_alive_thread.abort();
+        while self.response_receivers.close.try_recv().is_ok() {}
+        self.send_request(CloseRequest::new(self.session_id))?;
+        //Not every console version answers the logout, waiting only gives the request time to leave
+        let _ = tokio::time::timeout(CLOSE_TIMEOUT, self.response_receivers.close.recv()).await;
+        Ok(())
+    }
+
+    //Keep alive answers for our session carrying forceLogin as well as close messages mean the console dropped our session.
+    //Late answers for a session replaced before don't say anything about the current one
+    fn session_invalidated(&mut self) -> bool {
+        let mut invalidated = false;
+        while let Ok(response) = self.response_receivers.session_id.try_recv() {
+            if response.session == self.session_id && response.force_login.unwrap_or(false) {
+                invalidated = true;
+            }
+        }
+        while self.response_receivers.close.try_recv().is_ok() {
+            invalidated = true;
+        }
+        invalidated
+    }
+
+    //Logs in again on the existing websocket. Only if that fails the connection has to be reestablished
+    async fn restore_session(&mut self) -> Result<(), MaError> {
+        println!("MA2 session was invalidated. Logging in again...");
+        self.keep_alive_thread.abort();
+        let session_id = match MaInterface::get_session_id(&self.websocket_sender, &mut self.response_receivers, Some(self.session_id)).await {
+            Ok(session_id) => session_id,
+            Err(_) => return Err(MaError::SessionLost),
+        };
+        if MaInterface::login(&self.websocket_sender, &mut self.response_receivers, &self.login_credentials, &session_id, self.max_requests).await.is_err() {
+            return Err(MaError::SessionLost);
+        }
+        self.session_id = session_id;
+        self.keep_alive_thread = tokio::spawn(MaInterface::keep_alive_loop(self.websocket_sender.clone(), session_id));
+        while self.response_receivers.close.try_recv().is_ok() {}
+        println!("MA2 session restored with id {}", session_id);
+        Ok(())
+    }
+
+    //Midi surfaces clear their leds on page changes, so the next poll has to report every executor again
+    fn change_page(&mut self, page_change: PageChange) {
+        let page_index = match page_change {
+            PageChange::Next => self.page_index.saturating_add(1),
+            PageChange::Previous => self.page_index.saturating_sub(1),
+            PageChange::Goto(page_index) => page_index,
+        };
+        if page_index != self.page_index {
+            self.page_index = page_index;
+            self.reset_changes();
+        }
+    }
+
+    //Only page changes made on the console are adopted, so a page selected from midi stays active until the console switches again
+    fn receive_console_page_index(&mut self, console_page_index: u32) {
+        if self.follow_console_page && self.console_page_index != Some(console_page_index) {
+            self.page_index = console_page_index;
+        }
+        self.console_page_index = Some(console_page_index);
+    }
+
+    fn send_fader_value(&mut self, fader_value: &FaderValue) -> Result<(), MaError> {
+        let request = FaderInputRequest::new(self.session_id, fader_value.exec_index, self.page_index, fader_value.fader_value);
+        let key = CoalesceKey::FaderInput {
+            page_index: self.page_index,
+            exec_index: fader_value.exec_index,
+        };
+        self.send_coalesced_request(key, request)?;
         Ok(())
     }
 
-    fn send_button_value(&mut self, button_value: &ButtonValue) -> Result<(), Box<dyn Error>> {
-        let request = ButtonInputRequest::new(self.session_id, button_value, 0);
+    fn send_button_value(&mut self, button_value: &ButtonValue) -> Result<(), MaError> {
+        let request = ButtonInputRequest::new(self.session_id, button_value, self.page_index);
         self.send_request(request)?;
         Ok(())
     }
 
-    async fn keep_alive_loop(tx: UnboundedSender<Message>, session_id: i32) {
+    async fn keep_alive_loop(tx: UnboundedSender<ScheduledRequest>, session_id: i32) {
         let request = SessionIdRequest::new(&session_id);
         let request_string: String = serde_json::to_string(&request).unwrap();
         let mut interval = interval(Duration::from_millis(4000));
         loop {
             interval.tick().await;
-            let send_result = tx.send(Message::text(&request_string));
+            let send_result = tx.send(ScheduledRequest {
+                key: None,
+                response: SessionIdRequest::RESPONSE,
+                message: Message::text(&request_string),
+            });
             if let Err(e) = send_result {
                 println!("Keep alive thread exited with error: {:?}", e);
                 break;
@@ -178,99 +442,110 @@ impl MaInterface {
         }
     }
 
-    async fn get_session_id(tx: &UnboundedSender<Message>, rx: &mut ResponseReceivers) -> Result<i32, Box<dyn Error>> {
+    //Keep alive answers for the dropped session can still arrive after the request, they must not be taken for the new session.
+    //A new session is announced with forceLogin, so only the session id tells them apart
+    async fn get_session_id(tx: &UnboundedSender<ScheduledRequest>, rx: &mut ResponseReceivers, previous_session_id: Option<i32>) -> Result<i32, MaError> {
+        while rx.session_id.try_recv().is_ok() {}
         let request = SessionIdRequest::new_unknown_session();
         MaInterface::send_request_to_channel(tx, request)?;
-        let next = rx.session_id.recv().await;
-        if let Some(response) = next {
-            Ok(response.session)
-        } else {
-            Err("session_id_request EOS".into())
+        let next = tokio::time::timeout(RESPONSE_TIMEOUT, async {
+            while let Some(response) = rx.session_id.recv().await {
+                if Some(response.session) != previous_session_id {
+                    return Some(response.session);
+                }
+            }
+            None
+        })
+        .await;
+        match next {
+            Ok(Some(session_id)) => Ok(session_id),
+            Ok(None) => Err(MaError::ChannelClosed),
+            Err(_) => Err(MaError::Timeout),
         }
     }
 
-    async fn login(tx: &UnboundedSender<Message>, rx: &mut ResponseReceivers, credentials: &LoginCredentials, session_id: &i32) -> Result<(), Box<dyn Error>> {
-        let request = LoginRequest::new(credentials, session_id);
+    async fn login(tx: &UnboundedSender<ScheduledRequest>, rx: &mut ResponseReceivers, credentials: &LoginCredentials, session_id: &i32, max_requests: u32) -> Result<(), MaError> {
+        while rx.login.try_recv().is_ok() {}
+        let request = LoginRequest::new(credentials, session_id, max_requests);
         MaInterface::send_request_to_channel(tx, request)?;
-        let next = rx.login.recv().await;
-        if let Some(response) = next {
-            return if response.result { Ok(()) } else { Err("login invalid credentials".into()) };
+        let next = tokio::time::timeout(RESPONSE_TIMEOUT, rx.login.recv()).await;
+        match next {
+            Ok(Some(response)) => if response.result { Ok(()) } else { Err(MaError::AuthRejected) },
+            Ok(None) => Err(MaError::ChannelClosed),
+            Err(_) => Err(MaError::Timeout),
         }
-        Err("login EOS".into())
     }
 
-    async fn receive_loop(mut connection: Connection, response_senders: ResponseSenders) {
-        loop {
-            let next = connection.rx.next().await;
-            if let Some(result) = next {
-                match result {
-                    Ok(message) => {
-                        if MaInterface::receive_message(message, &response_senders).await.is_err() {
-                            break;
-                        }
-                    }
-                    Err(e) => {
-                        let _ = tokio::io::stdout().write_all(format!("receive loop error: {:?}", e).as_bytes()).await;
-                    }
-                }
-            } else {
+    async fn receive_loop(mut connection: Connection, response_senders: ResponseSenders, received_tx: UnboundedSender<ResponseKind>, last_received: Arc<Mutex<LastReceived>>, undecodable_frames: Arc<Mutex<u64>>) {
+        while let Some(message) = connection.rx.recv().await {
+            if MaInterface::receive_message(message, &response_senders, &received_tx, &last_received, &undecodable_frames).await.is_err() {
                 break;
             }
         }
     }
 
-    async fn receive_message(message: Message, response_senders: &ResponseSenders) -> Result<(), Box<dyn Error>> {
-        let response_with_explicit_type = serde_json::from_str::<ResponseWithExplicitType>(&message.to_string());
-        match response_with_explicit_type {
-            Ok(response) => {
-                if let Ok(request_type) = RequestType::from_str(&response.response_type) {
-                    MaInterface::receive_message_with_type(message, request_type, response_senders)?;
-                }
+    //Only answers free a slot in the request window, realtime pushes and greetings don't
+    async fn receive_message(message: Message, response_senders: &ResponseSenders, received_tx: &UnboundedSender<ResponseKind>, last_received: &Mutex<LastReceived>, undecodable_frames: &Mutex<u64>) -> Result<(), MaError> {
+        let frame = match message {
+            Message::Text(text) => text,
+            Message::Binary(data) => String::from_utf8_lossy(&data).into_owned(),
+            _ => return Ok(()),
+        };
+        match decode_frame(&frame) {
+            Ok(DecodedFrame::Login(login_response)) => {
+                received_tx.send(ResponseKind::Login)?;
+                response_senders.login.send(login_response)?;
             }
-            Err(_) => {
-                if let Ok(session_id_response) = serde_json::from_str::<SessionIdResponse>(&message.to_string()) {
-                    let send_result = response_senders.session_id.send(session_id_response);
-                    if send_result.is_err() {
-                        return Err("session id response channel closed".into());
-                    }
-                } else if !message.to_string().is_empty() {}
+            Ok(DecodedFrame::Command(command_response)) => {
+                received_tx.send(ResponseKind::Command)?;
+                response_senders.command.send(command_response)?;
             }
-        }
-        Ok(())
-    }
-
-    fn receive_message_with_type(message: Message, request_type: RequestType, response_senders: &ResponseSenders) -> Result<(), Box<dyn Error>> {
-        match request_type {
-            RequestType::Login => {
-                let login_response = serde_json::from_str::<LoginRequestResponse>(&message.to_string())?;
-                let send_result = response_senders.login.send(login_response);
-                if send_result.is_err() {
-                    return Err("login response channel closed".into());
-                }
-                Ok(())
+            Ok(DecodedFrame::Playbacks(playbacks_response)) => {
+                received_tx.send(ResponseKind::Playbacks)?;
+                response_senders.playbacks.send(*playbacks_response)?;
+                last_received.lock().await.playbacks = Instant::now();
             }
-            RequestType::Playbacks => {
-                let playbacks_response = serde_json::from_str::<PlaybacksResponse>(&message.to_string())?;
-                let send_result = response_senders.playbacks.send(playbacks_response);
-                if send_result.is_err() {
-                    return Err("playbacks response channel closed".into());
-                }
-                Ok(())
+            Ok(DecodedFrame::Close) => {
+                received_tx.send(ResponseKind::Close)?;
+                response_senders.close.send(())?;
             }
-            _ => {
-                Err(format!("Request Type unknown '{}'", request_type.to_string()).into())
+            Ok(DecodedFrame::SessionId(session_id_response)) => {
+                received_tx.send(ResponseKind::SessionId)?;
+                response_senders.session_id.send(session_id_response)?;
+                last_received.lock().await.keep_alive = Instant::now();
+            }
+            Ok(DecodedFrame::Ignored) => {}
+            Err(e) => {
+                let mut undecodable_frames = undecodable_frames.lock().await;
+                *undecodable_frames += 1;
+                let excerpt: String = frame.chars().take(200).collect();
+                println!("Undecodable frame #{} from MA2 ({}): {}", *undecodable_frames, e, excerpt);
             }
         }
+        Ok(())
     }
 
-    fn send_request<T: Serialize>(&self, request: T) -> Result<(), Box<dyn Error>> {
+    fn send_request<T: Request>(&self, request: T) -> Result<(), MaError> {
         MaInterface::send_request_to_channel(&self.websocket_sender, request)
     }
 
-    fn send_request_to_channel<T: Serialize>(tx: &UnboundedSender<Message>, request: T) -> Result<(), Box<dyn Error>> {
+    fn send_coalesced_request<T: Request>(&self, key: CoalesceKey, request: T) -> Result<(), MaError> {
+        let json_string = serde_json::to_string(&request)?;
+        self.websocket_sender.send(ScheduledRequest {
+            key: Some(key),
+            response: T::RESPONSE,
+            message: Message::text(json_string),
+        })?;
+        Ok(())
+    }
+
+    fn send_request_to_channel<T: Request>(tx: &UnboundedSender<ScheduledRequest>, request: T) -> Result<(), MaError> {
         let json_string = serde_json::to_string(&request)?;
-        let message = Message::text(json_string);
-        tx.send(message)?;
+        tx.send(ScheduledRequest {
+            key: None,
+            response: T::RESPONSE,
+            message: Message::text(json_string),
+        })?;
         Ok(())
     }
 }
@@ -279,5 +554,6 @@ impl Drop for MaInterface {
     fn drop(&mut self) {
         self.keep_alive_thread.abort();
         self.receiver_thread.abort();
+        self.scheduler_thread.abort();
     }
 }
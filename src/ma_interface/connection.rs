@@ -1,43 +1,97 @@
 use futures_util::stream::{SplitSink, SplitStream};
 use futures_util::{SinkExt, StreamExt};
-use std::error::Error;
+use super::endpoint::Endpoint;
+use super::error::MaError;
+use super::recording::{read_capture, replay_loop, Capture, FrameDirection};
 use tokio::net::TcpStream;
 use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
 use tokio::task::JoinHandle;
-use tokio_tungstenite::{connect_async, tungstenite::protocol::Message, MaybeTlsStream, WebSocketStream};
-use url::Url;
+use tokio_tungstenite::{connect_async_tls_with_config, tungstenite::protocol::Message, Connector, MaybeTlsStream, WebSocketStream};
 
 pub struct Connection {
     pub tx: UnboundedSender<Message>,
-    pub rx: SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>,
+    pub rx: UnboundedReceiver<Message>,
 
     forward_thread: JoinHandle<()>,
+    receive_thread: Option<JoinHandle<()>>,
 }
 
 impl Connection {
-    pub async fn new(url: &Url) -> Result<Connection, Box<dyn Error>> {
+    pub async fn new(endpoint: &Endpoint) -> Result<Connection, MaError> {
+        if let Some(replay_file) = &endpoint.replay_file {
+            return Connection::replay(replay_file).await;
+        }
+        let capture = match &endpoint.capture_file {
+            Some(capture_file) => Some(Capture::open(capture_file).await?),
+            None => None,
+        };
         let (tx_pipe_in, tx_pipe_out) = tokio::sync::mpsc::unbounded_channel();
-        let (ws_stream, _) = connect_async(url).await?;
+        let (rx_pipe_in, rx_pipe_out) = tokio::sync::mpsc::unbounded_channel();
+        let connector = endpoint.tls_connector.clone().map(Connector::NativeTls);
+        let (ws_stream, _) = connect_async_tls_with_config(&endpoint.url, None, connector).await?;
         let (socket_tx, socket_rx) = ws_stream.split();
-        let forward_thread = tokio::spawn(forward_loop(socket_tx, tx_pipe_out));
+        let forward_thread = tokio::spawn(forward_loop(socket_tx, tx_pipe_out, capture.clone()));
+        let receive_thread = tokio::spawn(receive_loop(socket_rx, rx_pipe_in, capture));
 
         Ok(Connection {
             tx: tx_pipe_in,
-            rx: socket_rx,
+            rx: rx_pipe_out,
             forward_thread,
+            receive_thread: Some(receive_thread),
         })
     }
+
+    //Stands in for the console by playing back a capture file
+    async fn replay(replay_file: &std::path::Path) -> Result<Connection, MaError> {
+        let frames = read_capture(replay_file).await?;
+        println!("Replaying {} frames from {}", frames.len(), replay_file.display());
+        let (tx_pipe_in, tx_pipe_out) = tokio::sync::mpsc::unbounded_channel();
+        let (rx_pipe_in, rx_pipe_out) = tokio::sync::mpsc::unbounded_channel();
+        let forward_thread = tokio::spawn(replay_loop(frames, tx_pipe_out, rx_pipe_in));
+        Ok(Connection {
+            tx: tx_pipe_in,
+            rx: rx_pipe_out,
+            forward_thread,
+            receive_thread: None,
+        })
+    }
+}
+
+async fn forward_loop(mut socket_tx: SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>, mut tx_pipe_out: UnboundedReceiver<Message>, capture: Option<Capture>) {
+    while let Some(message) = tx_pipe_out.recv().await {
+        if let Some(capture) = &capture {
+            capture.record(FrameDirection::Sent, &message);
+        }
+        if let Err(e) = socket_tx.send(message).await {
+            println!("Sending to MA2 failed: {:?}", e);
+            break;
+        }
+    }
 }
 
-async fn forward_loop(mut socket_tx: SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>, mut tx_pipe_out: UnboundedReceiver<Message>) {
-    loop {
-        let message = tx_pipe_out.recv().await.unwrap();
-        socket_tx.send(message).await.unwrap();
+async fn receive_loop(mut socket_rx: SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>, rx_pipe_in: UnboundedSender<Message>, capture: Option<Capture>) {
+    while let Some(result) = socket_rx.next().await {
+        match result {
+            Ok(message) => {
+                if let Some(capture) = &capture {
+                    capture.record(FrameDirection::Received, &message);
+                }
+                if rx_pipe_in.send(message).is_err() {
+                    break;
+                }
+            }
+            Err(e) => {
+                println!("receive loop error: {:?}", e);
+            }
+        }
     }
 }
 
 impl Drop for Connection {
     fn drop(&mut self) {
         self.forward_thread.abort();
+        if let Some(receive_thread) = &self.receive_thread {
+            receive_thread.abort();
+        }
     }
 }
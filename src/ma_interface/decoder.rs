@@ -0,0 +1,75 @@
+use super::requests::RequestType;
+use super::responses::{CommandResponse, LoginRequestResponse, PlaybacksResponse, SessionIdResponse};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+pub enum DecodedFrame {
+    Login(LoginRequestResponse),
+    Command(CommandResponse),
+    Playbacks(Box<PlaybacksResponse>),
+    Close,
+    SessionId(SessionIdResponse),
+    //Frames without meaning for midiMA, like the greeting sent after connecting
+    Ignored,
+}
+
+#[derive(Debug)]
+pub enum DecodeError {
+    Json(serde_json::Error),
+    NotAnObject,
+    UnknownResponseType(String),
+    Unrecognized,
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::Json(e) => write!(f, "invalid json: {}", e),
+            DecodeError::NotAnObject => write!(f, "frame is not a json object"),
+            DecodeError::UnknownResponseType(response_type) => write!(f, "unknown response type '{}'", response_type),
+            DecodeError::Unrecognized => write!(f, "frame has neither a response type nor a session"),
+        }
+    }
+}
+
+impl From<serde_json::Error> for DecodeError {
+    fn from(e: serde_json::Error) -> Self {
+        DecodeError::Json(e)
+    }
+}
+
+//The console doesn't always send valid json. Frames are parsed into a Value first, which keeps the last value of
+//duplicated keys (e.g. worldIndex in session id responses) instead of rejecting the whole frame
+pub fn decode_frame(frame: &str) -> Result<DecodedFrame, DecodeError> {
+    //Some frames are padded with NUL characters
+    let frame = frame.trim_matches(|c: char| c.is_whitespace() || c == '\0');
+    if frame.is_empty() {
+        return Ok(DecodedFrame::Ignored);
+    }
+    let Value::Object(object) = serde_json::from_str::<Value>(frame)? else {
+        return Err(DecodeError::NotAnObject);
+    };
+    if let Some(response_type) = object.get("responseType").and_then(Value::as_str) {
+        return match RequestType::from_str(response_type) {
+            Ok(RequestType::Login) => Ok(DecodedFrame::Login(from_object(object)?)),
+            Ok(RequestType::Command) => Ok(DecodedFrame::Command(from_object(object)?)),
+            Ok(RequestType::Playbacks) => Ok(DecodedFrame::Playbacks(Box::new(from_object(object)?))),
+            Ok(RequestType::Close) => Ok(DecodedFrame::Close),
+            Err(_) => Err(DecodeError::UnknownResponseType(response_type.to_string())),
+        };
+    }
+    if object.contains_key("session") {
+        return Ok(DecodedFrame::SessionId(from_object(object)?));
+    }
+    //Greeting after connecting and bare realtime notifications
+    if object.contains_key("status") || object.keys().all(|key| key == "realtime") {
+        return Ok(DecodedFrame::Ignored);
+    }
+    Err(DecodeError::Unrecognized)
+}
+
+fn from_object<T: DeserializeOwned>(object: Map<String, Value>) -> Result<T, DecodeError> {
+    Ok(serde_json::from_value(Value::Object(object))?)
+}
@@ -0,0 +1,188 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::PathBuf;
+use native_tls::{Certificate, TlsConnector};
+use url::Url;
+use crate::config::{ConsoleAddress, TlsConfig, WebsocketScheme};
+
+//Validated websocket address of a console together with the tls connector used for wss.
+//With a replay file set, the capture is played back instead of connecting to the console
+#[derive(Clone)]
+pub struct Endpoint {
+    pub url: Url,
+    pub tls_connector: Option<TlsConnector>,
+    pub capture_file: Option<PathBuf>,
+    pub replay_file: Option<PathBuf>,
+}
+
+impl Endpoint {
+    pub fn from_address(address: &ConsoleAddress) -> Result<Endpoint, EndpointError> {
+        let scheme = address.scheme.unwrap_or(WebsocketScheme::Ws);
+        let host = address.host.trim();
+        if host.is_empty() {
+            return Err(EndpointError::InvalidAddress("host is empty".to_string()));
+        }
+        if host.contains("://") || host.contains('/') {
+            return Err(EndpointError::InvalidAddress(format!("host '{}' must not contain a scheme or path", host)));
+        }
+        let scheme_name = match scheme {
+            WebsocketScheme::Ws => "ws",
+            WebsocketScheme::Wss => "wss",
+        };
+        //Bare IPv6 addresses have to be bracketed inside the url
+        let host = if host.matches(':').count() > 1 && !host.starts_with('[') { format!("[{}]", host) } else { host.to_string() };
+        let mut url = Url::parse(&format!("{}://{}", scheme_name, host)).map_err(|e| EndpointError::InvalidAddress(format!("host '{}' is invalid: {}", host, e)))?;
+        if let Some(port) = address.port {
+            if port == 0 {
+                return Err(EndpointError::InvalidAddress("port 0 is invalid".to_string()));
+            }
+            if url.set_port(Some(port)).is_err() {
+                return Err(EndpointError::InvalidAddress(format!("port can't be set for host '{}'", host)));
+            }
+        }
+        if let Some(path) = &address.path {
+            if path.contains('?') || path.contains('#') {
+                return Err(EndpointError::InvalidAddress(format!("path '{}' must not contain a query or fragment", path)));
+            }
+            url.set_path(path);
+        }
+        let tls_connector = match scheme {
+            WebsocketScheme::Wss => Some(create_tls_connector(&address.tls.clone().unwrap_or_default())?),
+            WebsocketScheme::Ws => {
+                if address.tls.is_some() {
+                    return Err(EndpointError::InvalidAddress("tls settings require the Wss scheme".to_string()));
+                }
+                None
+            }
+        };
+        Ok(Endpoint {
+            url,
+            tls_connector,
+            capture_file: None,
+            replay_file: None,
+        })
+    }
+}
+
+fn create_tls_connector(config: &TlsConfig) -> Result<TlsConnector, EndpointError> {
+    let mut builder = TlsConnector::builder();
+    builder.danger_accept_invalid_certs(!config.verify_certificate.unwrap_or(true));
+    builder.danger_accept_invalid_hostnames(!config.verify_hostname.unwrap_or(true));
+    if let Some(ca_certificate) = &config.ca_certificate {
+        let pem = fs::read(ca_certificate).map_err(|e| EndpointError::CaCertificate(ca_certificate.clone(), e.to_string()))?;
+        let certificate = Certificate::from_pem(&pem).map_err(|e| EndpointError::CaCertificate(ca_certificate.clone(), e.to_string()))?;
+        builder.add_root_certificate(certificate);
+    }
+    builder.build().map_err(|e| EndpointError::Tls(e.to_string()))
+}
+
+#[derive(Debug)]
+pub enum EndpointError {
+    InvalidAddress(String),
+    CaCertificate(String, String),
+    Tls(String),
+}
+
+impl Display for EndpointError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EndpointError::InvalidAddress(message) => write!(f, "Invalid console address: {}", message),
+            EndpointError::CaCertificate(file, message) => write!(f, "Can't load CA certificate '{}': {}", file, message),
+            EndpointError::Tls(message) => write!(f, "Can't set up TLS: {}", message),
+        }
+    }
+}
+
+impl Error for EndpointError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn address(host: &str) -> ConsoleAddress {
+        ConsoleAddress {
+            host: host.to_string(),
+            port: None,
+            scheme: None,
+            path: None,
+            tls: None,
+        }
+    }
+
+    fn invalid(address: &ConsoleAddress) -> bool {
+        matches!(Endpoint::from_address(address), Err(EndpointError::InvalidAddress(_)))
+    }
+
+    #[test]
+    fn defaults_to_plain_websocket() {
+        let endpoint = Endpoint::from_address(&address("192.168.0.10")).unwrap();
+        assert_eq!(endpoint.url.as_str(), "ws://192.168.0.10/");
+        assert!(endpoint.tls_connector.is_none());
+    }
+
+    #[test]
+    fn formats_port_and_path() {
+        let endpoint = Endpoint::from_address(&ConsoleAddress {
+            port: Some(8080),
+            path: Some("/remote".to_string()),
+            ..address("console.local")
+        })
+        .unwrap();
+        assert_eq!(endpoint.url.as_str(), "ws://console.local:8080/remote");
+    }
+
+    #[test]
+    fn brackets_ipv6_hosts() {
+        let endpoint = Endpoint::from_address(&ConsoleAddress { port: Some(8080), ..address("fe80::1") }).unwrap();
+        assert_eq!(endpoint.url.as_str(), "ws://[fe80::1]:8080/");
+    }
+
+    #[test]
+    fn rejects_missing_host() {
+        assert!(invalid(&address("")));
+        assert!(invalid(&address("  ")));
+    }
+
+    #[test]
+    fn rejects_scheme_or_path_in_host() {
+        assert!(invalid(&address("ws://192.168.0.10")));
+        assert!(invalid(&address("192.168.0.10/remote")));
+    }
+
+    #[test]
+    fn rejects_port_zero() {
+        assert!(invalid(&ConsoleAddress { port: Some(0), ..address("192.168.0.10") }));
+    }
+
+    #[test]
+    fn rejects_query_in_path() {
+        assert!(invalid(&ConsoleAddress { path: Some("/remote?session=1".to_string()), ..address("192.168.0.10") }));
+    }
+
+    #[test]
+    fn rejects_tls_settings_without_wss() {
+        assert!(invalid(&ConsoleAddress { tls: Some(TlsConfig::default()), ..address("192.168.0.10") }));
+        let endpoint = Endpoint::from_address(&ConsoleAddress {
+            scheme: Some(WebsocketScheme::Wss),
+            tls: Some(TlsConfig::default()),
+            ..address("192.168.0.10")
+        })
+        .unwrap();
+        assert_eq!(endpoint.url.scheme(), "wss");
+        assert!(endpoint.tls_connector.is_some());
+    }
+
+    #[test]
+    fn reports_missing_ca_certificate() {
+        let result = Endpoint::from_address(&ConsoleAddress {
+            scheme: Some(WebsocketScheme::Wss),
+            tls: Some(TlsConfig {
+                ca_certificate: Some("does-not-exist.pem".to_string()),
+                ..TlsConfig::default()
+            }),
+            ..address("192.168.0.10")
+        });
+        assert!(matches!(result, Err(EndpointError::CaCertificate(file, _)) if file == "does-not-exist.pem"));
+    }
+}
@@ -0,0 +1,58 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use tokio::sync::mpsc::error::SendError;
+use tokio_tungstenite::tungstenite;
+
+#[derive(Debug)]
+pub enum MaError {
+    Connect(Box<tungstenite::Error>),
+    AuthRejected,
+    SessionLost,
+    Timeout,
+    Protocol(String),
+    ChannelClosed,
+    CommandRejected(String),
+    Recording(String),
+}
+
+impl MaError {
+    //Errors after which the websocket connection can't be used any more and has to be reestablished
+    pub fn requires_reconnect(&self) -> bool {
+        matches!(self, MaError::Connect(_) | MaError::SessionLost | MaError::Timeout | MaError::ChannelClosed)
+    }
+}
+
+impl Display for MaError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MaError::Connect(e) => write!(f, "Connection to MA2 failed: {}", e),
+            MaError::AuthRejected => write!(f, "MA2 rejected the login credentials."),
+            MaError::SessionLost => write!(f, "MA2 session was invalidated."),
+            MaError::Timeout => write!(f, "MA2 did not answer in time."),
+            MaError::Protocol(message) => write!(f, "Unexpected message from MA2: {}", message),
+            MaError::ChannelClosed => write!(f, "MA2 connection closed."),
+            MaError::CommandRejected(prompt) => write!(f, "MA2 rejected the command: {}", prompt),
+            MaError::Recording(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for MaError {}
+
+impl From<tungstenite::Error> for MaError {
+    fn from(e: tungstenite::Error) -> Self {
+        MaError::Connect(Box::new(e))
+    }
+}
+
+impl From<serde_json::Error> for MaError {
+    fn from(e: serde_json::Error) -> Self {
+        MaError::Protocol(e.to_string())
+    }
+}
+
+impl<T> From<SendError<T>> for MaError {
+    fn from(_: SendError<T>) -> Self {
+        MaError::ChannelClosed
+    }
+}
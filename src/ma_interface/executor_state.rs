@@ -0,0 +1,130 @@
+use super::objects::{CueItem, Cues, Executor, ExecutorBlock};
+use super::playbacks::ExecutorRecord;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+use tokio::sync::Mutex;
+
+#[derive(Clone, PartialEq)]
+pub struct ExecutorState {
+    pub name: String,
+    pub text_color: String,
+    pub background_color: String,
+    pub running: bool,
+    //Value text of the fader as shown on the console, e.g. "120 BPM", for displays
+    pub fader_text: Option<String>,
+    pub button_labels: [String; 3],
+    pub current_cue: Option<CueInfo>,
+    pub next_cue: Option<CueInfo>,
+}
+
+impl ExecutorState {
+    pub fn new(executor: &Executor, executor_block: &ExecutorBlock) -> Self {
+        Self {
+            name: executor.tt.t.clone(),
+            text_color: executor.text_color.clone(),
+            background_color: executor.background_color.clone(),
+            running: executor.isRun != 0,
+            fader_text: executor_block.fader.value_string.clone(),
+            button_labels: [
+                executor_block.button1.type_string.clone(),
+                executor_block.button2.type_string.clone(),
+                executor_block.button3.type_string.clone(),
+            ],
+            current_cue: CueInfo::current(&executor.cues),
+            next_cue: CueInfo::next(&executor.cues),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub struct CueInfo {
+    pub number: Option<String>,
+    pub name: String,
+    pub text_color: Option<String>,
+    pub progress: Option<f32>,
+}
+
+impl CueInfo {
+    //WebRemote sends the previous, current and next cue, leaving out the previous one at the start of a sequence
+    pub fn current(cues: &Cues) -> Option<CueInfo> {
+        let index = if cues.items.len() >= 3 { 1 } else { 0 };
+        cues.items.get(index).and_then(CueInfo::new)
+    }
+
+    pub fn next(cues: &Cues) -> Option<CueInfo> {
+        let index = if cues.items.len() >= 3 { 2 } else { 1 };
+        cues.items.get(index).and_then(CueInfo::new)
+    }
+
+    fn new(item: &CueItem) -> Option<CueInfo> {
+        let text = item.text.trim();
+        if text.is_empty() {
+            return None;
+        }
+        let (number, name) = text.split_once(' ').unwrap_or((text, ""));
+        let (number, name) = if number.parse::<f32>().is_ok() {
+            (Some(number.to_string()), name.trim().to_string())
+        } else {
+            (None, text.to_string())
+        };
+        Some(CueInfo {
+            number,
+            name,
+            text_color: item.text_color.clone(),
+            progress: item.progress.as_ref().and_then(|progress| progress.value),
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct ExecutorStateChange {
+    pub page_index: u32,
+    pub exec_index: u32,
+    pub state: ExecutorState,
+}
+
+#[derive(Default)]
+struct ExecutorStates {
+    states: HashMap<(u32, u32), ExecutorState>,
+    subscribers: Vec<UnboundedSender<ExecutorStateChange>>,
+}
+
+//Shared handle, clones refer to the same cache so subscriptions survive reconnects of the MaInterface
+#[derive(Clone, Default)]
+pub struct ExecutorStateCache {
+    inner: Arc<Mutex<ExecutorStates>>,
+}
+
+impl ExecutorStateCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn get(&self, page_index: u32, exec_index: u32) -> Option<ExecutorState> {
+        self.inner.lock().await.states.get(&(page_index, exec_index)).cloned()
+    }
+
+    pub async fn subscribe(&self) -> UnboundedReceiver<ExecutorStateChange> {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        self.inner.lock().await.subscribers.push(tx);
+        rx
+    }
+
+    pub async fn update(&self, records: &[ExecutorRecord]) {
+        let mut inner = self.inner.lock().await;
+        for record in records {
+            let key = (record.page_index, record.block_exec_index());
+            if inner.states.get(&key) == Some(&record.state) {
+                continue;
+            }
+            inner.states.insert(key, record.state.clone());
+            let change = ExecutorStateChange {
+                page_index: record.page_index,
+                exec_index: record.block_exec_index(),
+                state: record.state.clone(),
+            };
+            inner.subscribers.retain(|subscriber| subscriber.send(change.clone()).is_ok());
+        }
+    }
+}
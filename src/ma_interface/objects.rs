@@ -19,6 +19,7 @@ pub struct Executor {
     pub text_color: String,
     #[serde(rename = "bdC")]
     pub background_color: String,
+    #[serde(default)]
     pub cues: Cues,
     #[serde(rename = "combinedItems")]
     pub combined_executor_blocks: i32,
@@ -40,8 +41,33 @@ pub struct UnknownType2 {
     pub t: String,
 }
 
+#[derive(Serialize, Deserialize, Default)]
+pub struct Cues {
+    #[serde(rename = "bC")]
+    pub background_color: Option<String>,
+    #[serde(rename = "pC")]
+    pub progress_color: Option<String>,
+    #[serde(default)]
+    pub items: Vec<CueItem>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CueItem {
+    #[serde(rename = "t", default)]
+    pub text: String,
+    #[serde(rename = "c")]
+    pub text_color: Option<String>,
+    #[serde(rename = "pgs")]
+    pub progress: Option<CueProgress>,
+}
+
 #[derive(Serialize, Deserialize)]
-pub struct Cues {}
+pub struct CueProgress {
+    #[serde(rename = "v")]
+    pub value: Option<f32>,
+    #[serde(rename = "bC")]
+    pub background_color: Option<String>,
+}
 
 #[derive(Serialize, Deserialize)]
 pub struct ExecutorBlock {
@@ -0,0 +1,59 @@
+use super::executor_state::ExecutorState;
+use super::FaderRange;
+use super::objects::Executor;
+use crate::config::ButtonPosition;
+use super::responses::PlaybacksResponse;
+
+#[derive(Clone)]
+pub struct ExecutorRecord {
+    pub page_index: u32,
+    pub exec_index: u32,
+    pub block_index: u32,
+    pub fader_value: f32,
+    pub fader_range: FaderRange,
+    pub button_states: [bool; 3],
+    pub state: ExecutorState,
+}
+
+impl ExecutorRecord {
+    //iPage is the page shown on the console, the executors are those of the requested page
+    pub fn from_response(page_index: u32, response: &PlaybacksResponse) -> Vec<ExecutorRecord> {
+        let mut records = Vec::new();
+        for group in &response.itemGroups {
+            for group_of_five in &group.items {
+                for executor in group_of_five {
+                    records.extend(Self::from_executor(page_index, executor));
+                }
+            }
+        }
+        records
+    }
+
+    fn from_executor(page_index: u32, executor: &Executor) -> impl Iterator<Item = ExecutorRecord> + '_ {
+        executor.executor_blocks.iter().enumerate().map(move |(block_index, executor_block)| ExecutorRecord {
+            page_index,
+            exec_index: executor.iExec as u32,
+            block_index: block_index as u32,
+            fader_value: executor_block.fader.value,
+            fader_range: FaderRange {
+                min: executor_block.fader.min as f32,
+                max: executor_block.fader.max as f32,
+            },
+            button_states: [executor_block.button1.pressed, executor_block.button2.pressed, executor_block.button3.pressed],
+            state: ExecutorState::new(executor, executor_block),
+        })
+    }
+
+    //Blocks of combined executors are addressed by the index of the executor they are displayed as
+    pub fn block_exec_index(&self) -> u32 {
+        self.exec_index + self.block_index
+    }
+
+    pub fn button_state(&self, position: ButtonPosition) -> bool {
+        match position {
+            ButtonPosition::Bottom => self.button_states[0],
+            ButtonPosition::Mid => self.button_states[1],
+            ButtonPosition::Top => self.button_states[2],
+        }
+    }
+}
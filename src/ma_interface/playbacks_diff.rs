@@ -0,0 +1,102 @@
+use super::executor_state::ExecutorState;
+use super::playbacks::ExecutorRecord;
+use super::{ButtonValue, FaderRange, FaderValue, Update};
+use crate::config::ButtonPosition;
+use std::collections::HashMap;
+
+#[derive(Clone)]
+pub enum ExecutorEvent {
+    FaderChanged {
+        page_index: u32,
+        exec_index: u32,
+        value: f32,
+        range: FaderRange,
+    },
+    ButtonChanged {
+        page_index: u32,
+        exec_index: u32,
+        position: ButtonPosition,
+        pressed: bool,
+    },
+    StateChanged {
+        page_index: u32,
+        exec_index: u32,
+        state: Box<ExecutorState>,
+    },
+}
+
+impl ExecutorEvent {
+    pub fn to_update(&self) -> Option<Update> {
+        match self {
+            ExecutorEvent::FaderChanged { exec_index, value, range, .. } => Some(Update::FaderUpdate(FaderValue {
+                fader_value: *value,
+                exec_index: u8::try_from(*exec_index).ok()?,
+                range: Some(*range),
+            })),
+            ExecutorEvent::ButtonChanged { exec_index, position, pressed, .. } => Some(Update::ButtonUpdate(ButtonValue {
+                button_value: *pressed,
+                exec_index: u8::try_from(*exec_index).ok()?,
+                position: *position,
+            })),
+            ExecutorEvent::StateChanged { .. } => None,
+        }
+    }
+}
+
+//Keeps the last polled state per executor block and reports only what changed since the previous poll
+#[derive(Default)]
+pub struct PlaybacksDiff {
+    page_index: Option<u32>,
+    previous: HashMap<u32, ExecutorRecord>,
+}
+
+impl PlaybacksDiff {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn reset(&mut self) {
+        self.page_index = None;
+        self.previous.clear();
+    }
+
+    pub fn diff(&mut self, records: Vec<ExecutorRecord>) -> Vec<ExecutorEvent> {
+        let mut events = Vec::new();
+        for record in records {
+            if self.page_index != Some(record.page_index) {
+                self.reset();
+                self.page_index = Some(record.page_index);
+            }
+            let exec_index = record.block_exec_index();
+            let previous = self.previous.get(&exec_index);
+            if previous.is_none_or(|previous| previous.fader_value != record.fader_value || previous.fader_range != record.fader_range) {
+                events.push(ExecutorEvent::FaderChanged {
+                    page_index: record.page_index,
+                    exec_index,
+                    value: record.fader_value,
+                    range: record.fader_range,
+                });
+            }
+            for position in [ButtonPosition::Bottom, ButtonPosition::Mid, ButtonPosition::Top] {
+                let pressed = record.button_state(position);
+                if previous.is_none_or(|previous| previous.button_state(position) != pressed) {
+                    events.push(ExecutorEvent::ButtonChanged {
+                        page_index: record.page_index,
+                        exec_index,
+                        position,
+                        pressed,
+                    });
+                }
+            }
+            if previous.is_none_or(|previous| previous.state != record.state) {
+                events.push(ExecutorEvent::StateChanged {
+                    page_index: record.page_index,
+                    exec_index,
+                    state: Box::new(record.state.clone()),
+                });
+            }
+            self.previous.insert(exec_index, record);
+        }
+        events
+    }
+}
@@ -0,0 +1,138 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::fs::{File, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+use tokio_tungstenite::tungstenite::protocol::Message;
+use super::error::MaError;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum FrameDirection {
+    Sent,
+    Received,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum FrameKind {
+    #[default]
+    Text,
+    Binary,
+    Ping,
+    Pong,
+    Close,
+}
+
+//One line of a capture file. timestamp is given in milliseconds since the unix epoch, length is the payload size in bytes.
+//Binary payloads are kept as lossy utf8 like the decoder reads them, close frames keep their reason and pings and pongs
+//only their length. Captures made before other frames were recorded contain text frames only
+#[derive(Serialize, Deserialize)]
+pub struct CapturedFrame {
+    pub timestamp: u64,
+    pub direction: FrameDirection,
+    #[serde(default)]
+    pub kind: FrameKind,
+    #[serde(default)]
+    pub length: usize,
+    pub frame: String,
+}
+
+//Handle for writing frames to a capture file. The file is written by a background task, so capturing never blocks the connection
+#[derive(Clone)]
+pub struct Capture {
+    sender: UnboundedSender<CapturedFrame>,
+}
+
+impl Capture {
+    pub async fn open(path: &Path) -> Result<Capture, MaError> {
+        let file = OpenOptions::new().create(true).append(true).open(path).await
+            .map_err(|e| MaError::Recording(format!("Can't open capture file '{}': {}", path.display(), e)))?;
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        tokio::spawn(Self::write_loop(file, receiver));
+        Ok(Capture { sender })
+    }
+
+    pub fn record(&self, direction: FrameDirection, message: &Message) {
+        let (kind, frame) = match message {
+            Message::Text(text) => (FrameKind::Text, text.clone()),
+            Message::Binary(data) => (FrameKind::Binary, String::from_utf8_lossy(data).into_owned()),
+            Message::Ping(_) => (FrameKind::Ping, String::new()),
+            Message::Pong(_) => (FrameKind::Pong, String::new()),
+            Message::Close(close_frame) => (FrameKind::Close, close_frame.as_ref().map(|close_frame| close_frame.reason.to_string()).unwrap_or_default()),
+            //Raw frames are only used for writing and never reach the connection
+            Message::Frame(_) => return,
+        };
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+        let _ = self.sender.send(CapturedFrame {
+            timestamp,
+            direction,
+            kind,
+            length: message.len(),
+            frame,
+        });
+    }
+
+    async fn write_loop(mut file: File, mut receiver: UnboundedReceiver<CapturedFrame>) {
+        while let Some(frame) = receiver.recv().await {
+            let Ok(mut line) = serde_json::to_string(&frame) else {
+                continue;
+            };
+            line.push('\n');
+            //Flushing every frame keeps the capture complete even if midiMA is killed
+            let result = match file.write_all(line.as_bytes()).await {
+                Ok(()) => file.flush().await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                println!("Writing capture file failed: {}", e);
+                break;
+            }
+        }
+    }
+}
+
+pub async fn read_capture(path: &Path) -> Result<Vec<CapturedFrame>, MaError> {
+    let content = tokio::fs::read_to_string(path).await
+        .map_err(|e| MaError::Recording(format!("Can't read capture file '{}': {}", path.display(), e)))?;
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .enumerate()
+        .map(|(line_index, line)| serde_json::from_str::<CapturedFrame>(line)
+            .map_err(|e| MaError::Recording(format!("Invalid frame in line {} of '{}': {}", line_index + 1, path.display(), e))))
+        .collect()
+}
+
+//Plays the received frames of a capture back with their original spacing. Each sent frame of the capture waits for the
+//client to send a frame, so responses are not delivered before the matching request was made.
+//Pings, pongs and close frames are answered by the websocket itself, so they are not played back.
+//Once the capture is played back the connection stays open, so the state at its end can be inspected
+pub async fn replay_loop(frames: Vec<CapturedFrame>, mut sent: UnboundedReceiver<Message>, received: UnboundedSender<Message>) {
+    let mut previous_timestamp = None;
+    for frame in frames {
+        let message = match frame.kind {
+            FrameKind::Text => Message::text(frame.frame),
+            FrameKind::Binary => Message::binary(frame.frame),
+            FrameKind::Ping | FrameKind::Pong | FrameKind::Close => continue,
+        };
+        match frame.direction {
+            FrameDirection::Sent => {
+                if sent.recv().await.is_none() {
+                    return;
+                }
+            }
+            FrameDirection::Received => {
+                if let Some(previous_timestamp) = previous_timestamp {
+                    tokio::time::sleep(Duration::from_millis(frame.timestamp.saturating_sub(previous_timestamp))).await;
+                }
+                if received.send(message).is_err() {
+                    return;
+                }
+            }
+        }
+        previous_timestamp = Some(frame.timestamp);
+    }
+    println!("Replay finished");
+    while sent.recv().await.is_some() {}
+    drop(received);
+}
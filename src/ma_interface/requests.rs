@@ -1,9 +1,11 @@
 use crate::LoginCredentials;
 use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 use std::vec::Vec;
 use crate::config::ButtonPosition;
 use crate::ma_interface::ButtonValue;
+use crate::ma_interface::scheduler::ResponseKind;
 
 #[derive(PartialEq)]
 pub enum RequestType {
@@ -26,17 +28,22 @@ impl FromStr for RequestType {
     }
 }
 
-impl ToString for RequestType {
-    fn to_string(&self) -> std::string::String {
+impl Display for RequestType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            RequestType::Playbacks => String::from("playbacks"),
-            RequestType::Close => String::from("close"),
-            RequestType::Login => String::from("login"),
-            RequestType::Command => String::from("command"),
+            RequestType::Playbacks => write!(f, "playbacks"),
+            RequestType::Close => write!(f, "close"),
+            RequestType::Login => write!(f, "login"),
+            RequestType::Command => write!(f, "command"),
         }
     }
 }
 
+//Tells the scheduler which response answers a request, None for requests the console doesn't answer
+pub trait Request: Serialize {
+    const RESPONSE: Option<ResponseKind>;
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct LoginRequest {
     #[serde(rename = "requestType")]
@@ -45,14 +52,18 @@ pub struct LoginRequest {
     pub password: String,
     pub session: i32,
     #[serde(rename = "maxRequests")]
-    pub max_requests: i32,
+    pub max_requests: u32,
+}
+
+impl Request for LoginRequest {
+    const RESPONSE: Option<ResponseKind> = Some(ResponseKind::Login);
 }
 
 impl LoginRequest {
-    pub fn new(login_credentials: &LoginCredentials, session: &i32) -> LoginRequest {
+    pub fn new(login_credentials: &LoginCredentials, session: &i32, max_requests: u32) -> LoginRequest {
         LoginRequest {
             request_type: String::from("login"),
-            max_requests: 10,
+            max_requests,
             username: login_credentials.username.clone(),
             password: login_credentials.password_hash.clone(),
             session: *session,
@@ -65,6 +76,10 @@ pub struct SessionIdRequest {
     pub session: i32,
 }
 
+impl Request for SessionIdRequest {
+    const RESPONSE: Option<ResponseKind> = Some(ResponseKind::SessionId);
+}
+
 impl SessionIdRequest {
     pub fn new(id: &i32) -> SessionIdRequest {
         SessionIdRequest { session: *id }
@@ -77,6 +92,29 @@ impl SessionIdRequest {
     }
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct CloseRequest {
+    #[serde(rename = "requestType")]
+    pub request_type: String,
+    pub session: i32,
+    #[serde(rename = "maxRequests")]
+    pub max_requests: i32,
+}
+
+impl Request for CloseRequest {
+    const RESPONSE: Option<ResponseKind> = Some(ResponseKind::Close);
+}
+
+impl CloseRequest {
+    pub fn new(session: i32) -> Self {
+        Self {
+            request_type: RequestType::Close.to_string(),
+            session,
+            max_requests: 1,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct PlaybacksRequest {
     #[serde(rename = "requestType")]
@@ -97,6 +135,10 @@ pub struct PlaybacksRequest {
     pub session: i32,
 }
 
+impl Request for PlaybacksRequest {
+    const RESPONSE: Option<ResponseKind> = Some(ResponseKind::Playbacks);
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct FaderInputRequest {
     #[serde(rename = "requestType")]
@@ -112,6 +154,10 @@ pub struct FaderInputRequest {
     session: i32,
 }
 
+impl Request for FaderInputRequest {
+    const RESPONSE: Option<ResponseKind> = None;
+}
+
 impl FaderInputRequest {
     pub fn new(session: i32, exec_index: u8, page_index: u32, fader_value: f32) -> FaderInputRequest {
         FaderInputRequest {
@@ -143,6 +189,10 @@ pub struct ButtonInputRequest {
     pub session: i32,
 }
 
+impl Request for ButtonInputRequest {
+    const RESPONSE: Option<ResponseKind> = None;
+}
+
 impl ButtonInputRequest {
     pub fn new(session: i32, button: &ButtonValue, page_index: u32) -> Self {
         Self {
@@ -161,4 +211,29 @@ impl ButtonInputRequest {
             session
         }
     }
-}
\ No newline at end of file
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CommandRequest {
+    #[serde(rename = "requestType")]
+    pub request_type: String,
+    pub command: String,
+    pub session: i32,
+    #[serde(rename = "maxRequests")]
+    pub max_requests: i32,
+}
+
+impl Request for CommandRequest {
+    const RESPONSE: Option<ResponseKind> = Some(ResponseKind::Command);
+}
+
+impl CommandRequest {
+    pub fn new(session: i32, command: &str) -> Self {
+        Self {
+            request_type: RequestType::Command.to_string(),
+            command: command.to_string(),
+            session,
+            max_requests: 0,
+        }
+    }
+}
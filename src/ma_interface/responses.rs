@@ -7,7 +7,7 @@ pub struct ResponseWithExplicitType {
     pub response_type: String,
 }
 
-//Server sometimes sends SessionIdResponse with worldIndex as a duplicate entry which makes it invalid json
+//Server sometimes sends SessionIdResponse with worldIndex as a duplicate entry, see decoder.rs
 #[allow(non_snake_case)]
 #[derive(Serialize, Deserialize)]
 pub struct SessionIdResponse {
@@ -32,6 +32,25 @@ pub struct LoginRequestResponse {
     pub world_index: Option<i32>,
 }
 
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CommandResponse {
+    pub realtime: bool,
+    #[serde(rename = "responseType")]
+    pub response_type: String,
+    pub prompt: Option<String>,
+    #[serde(rename = "promptcolor")]
+    pub prompt_color: Option<String>,
+    #[serde(rename = "worldIndex")]
+    pub world_index: Option<i32>,
+}
+
+impl CommandResponse {
+    //MA2 reports rejected commands only through the prompt, e.g. "Error #1: UNKNOWN COMMAND"
+    pub fn error_message(&self) -> Option<&str> {
+        self.prompt.as_deref().filter(|prompt| prompt.trim_start().starts_with("Error"))
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 #[allow(non_snake_case)]
 pub struct ConnectResponse {
@@ -0,0 +1,245 @@
+use crate::config::SpecialMaster;
+use std::collections::VecDeque;
+use std::time::Duration;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+use tokio::time::{sleep_until, Instant};
+use tokio_tungstenite::tungstenite::protocol::Message;
+use super::RESPONSE_TIMEOUT;
+
+//Executor input isn't answered by the console. It still takes a slot for this long, so a moving fader can't flood
+//the console beyond the window
+const INPUT_SLOT_DURATION: Duration = Duration::from_millis(50);
+
+//Queued requests with the same key are replaced by the newest one instead of being sent one after another
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CoalesceKey {
+    FaderInput { page_index: u32, exec_index: u8 },
+    SpecialMaster(SpecialMaster),
+}
+
+//Responses that answer a request, each of them frees the slot of the oldest request waiting for this kind of response
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ResponseKind {
+    SessionId,
+    Login,
+    Command,
+    Playbacks,
+    Close,
+}
+
+pub struct ScheduledRequest {
+    pub key: Option<CoalesceKey>,
+    //None for requests the console doesn't answer, their slot is released after INPUT_SLOT_DURATION
+    pub response: Option<ResponseKind>,
+    pub message: Message,
+}
+
+pub struct RequestScheduler {
+    max_requests: usize,
+    socket_tx: UnboundedSender<Message>,
+    requests: UnboundedReceiver<ScheduledRequest>,
+    responses: UnboundedReceiver<ResponseKind>,
+    queue: VecDeque<ScheduledRequest>,
+    //Expected response and the time the slot is released at the latest. Answers can get lost or be rejected silently,
+    //so a slot is not held longer than the response is waited for
+    outstanding: VecDeque<(Option<ResponseKind>, Instant)>,
+}
+
+impl RequestScheduler {
+    pub fn new(max_requests: u32, socket_tx: UnboundedSender<Message>, requests: UnboundedReceiver<ScheduledRequest>, responses: UnboundedReceiver<ResponseKind>) -> Self {
+        Self {
+            max_requests: max_requests.max(1) as usize,
+            socket_tx,
+            requests,
+            responses,
+            queue: VecDeque::new(),
+            outstanding: VecDeque::new(),
+        }
+    }
+
+    pub async fn run(mut self) {
+        loop {
+            self.release_expired_slots();
+            if self.send_queued().is_err() {
+                break;
+            }
+            let next_expiry = match self.queue.is_empty() {
+                true => None,
+                false => self.outstanding.iter().map(|(_, expiry)| *expiry).min(),
+            };
+            tokio::select! {
+                request = self.requests.recv() => {
+                    match request {
+                        Some(request) => self.enqueue(request),
+                        None => break,
+                    }
+                }
+                response = self.responses.recv() => {
+                    match response {
+                        Some(response) => self.release_slot(response),
+                        None => break,
+                    }
+                }
+                _ = sleep_until(next_expiry.unwrap_or_else(Instant::now)), if next_expiry.is_some() => {}
+            }
+        }
+    }
+
+    fn enqueue(&mut self, request: ScheduledRequest) {
+        if let Some(key) = request.key {
+            if let Some(queued) = self.queue.iter_mut().find(|queued| queued.key == Some(key)) {
+                queued.message = request.message;
+                return;
+            }
+        }
+        self.queue.push_back(request);
+    }
+
+    fn release_slot(&mut self, response: ResponseKind) {
+        if let Some(position) = self.outstanding.iter().position(|(expected, _)| *expected == Some(response)) {
+            self.outstanding.remove(position);
+        }
+    }
+
+    fn release_expired_slots(&mut self) {
+        let now = Instant::now();
+        self.outstanding.retain(|(_, expiry)| *expiry > now);
+    }
+
+    fn send_queued(&mut self) -> Result<(), ()> {
+        while let Some(request) = self.queue.front() {
+            if self.outstanding.len() >= self.max_requests {
+                break;
+            }
+            let slot_duration = if request.response.is_some() { RESPONSE_TIMEOUT } else { INPUT_SLOT_DURATION };
+            let request = self.queue.pop_front().unwrap();
+            if self.socket_tx.send(request.message).is_err() {
+                return Err(());
+            }
+            self.outstanding.push_back((request.response, Instant::now() + slot_duration));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::mpsc::unbounded_channel;
+
+    fn scheduler(max_requests: u32) -> (RequestScheduler, UnboundedReceiver<Message>) {
+        let (socket_tx, socket_rx) = unbounded_channel();
+        let (_, requests) = unbounded_channel();
+        let (_, responses) = unbounded_channel();
+        (RequestScheduler::new(max_requests, socket_tx, requests, responses), socket_rx)
+    }
+
+    fn request(text: &str, response: Option<ResponseKind>) -> ScheduledRequest {
+        ScheduledRequest {
+            key: None,
+            response,
+            message: Message::text(text),
+        }
+    }
+
+    fn fader(page_index: u32, exec_index: u8, text: &str) -> ScheduledRequest {
+        ScheduledRequest {
+            key: Some(CoalesceKey::FaderInput { page_index, exec_index }),
+            response: None,
+            message: Message::text(text),
+        }
+    }
+
+    fn sent(socket_rx: &mut UnboundedReceiver<Message>) -> Vec<String> {
+        let mut sent = Vec::new();
+        while let Ok(message) = socket_rx.try_recv() {
+            sent.push(message.to_string());
+        }
+        sent
+    }
+
+    #[tokio::test]
+    async fn keeps_at_most_max_requests_unanswered() {
+        let (mut scheduler, mut socket_rx) = scheduler(2);
+        for text in ["a", "b", "c"] {
+            scheduler.enqueue(request(text, Some(ResponseKind::Playbacks)));
+        }
+        scheduler.send_queued().unwrap();
+        assert_eq!(sent(&mut socket_rx), ["a", "b"]);
+
+        scheduler.release_slot(ResponseKind::Playbacks);
+        scheduler.send_queued().unwrap();
+        assert_eq!(sent(&mut socket_rx), ["c"]);
+    }
+
+    #[tokio::test]
+    async fn releases_slots_only_for_matching_responses() {
+        let (mut scheduler, mut socket_rx) = scheduler(1);
+        scheduler.enqueue(request("playbacks", Some(ResponseKind::Playbacks)));
+        scheduler.enqueue(request("command", Some(ResponseKind::Command)));
+        scheduler.send_queued().unwrap();
+        assert_eq!(sent(&mut socket_rx), ["playbacks"]);
+
+        scheduler.release_slot(ResponseKind::SessionId);
+        scheduler.send_queued().unwrap();
+        assert!(sent(&mut socket_rx).is_empty());
+
+        scheduler.release_slot(ResponseKind::Playbacks);
+        scheduler.send_queued().unwrap();
+        assert_eq!(sent(&mut socket_rx), ["command"]);
+    }
+
+    #[tokio::test]
+    async fn sends_queued_requests_in_order() {
+        let (mut scheduler, mut socket_rx) = scheduler(2);
+        scheduler.enqueue(request("playbacks", Some(ResponseKind::Playbacks)));
+        scheduler.enqueue(request("login", Some(ResponseKind::Login)));
+        scheduler.enqueue(request("button", None));
+        scheduler.send_queued().unwrap();
+        assert_eq!(sent(&mut socket_rx), ["playbacks", "login"]);
+
+        scheduler.release_slot(ResponseKind::Playbacks);
+        scheduler.send_queued().unwrap();
+        assert_eq!(sent(&mut socket_rx), ["button"]);
+    }
+
+    #[tokio::test]
+    async fn holds_input_slots_briefly_and_answered_slots_until_the_response_timeout() {
+        let (mut scheduler, mut socket_rx) = scheduler(1);
+        scheduler.enqueue(request("button", None));
+        scheduler.enqueue(request("playbacks", Some(ResponseKind::Playbacks)));
+        scheduler.enqueue(request("fader", None));
+        scheduler.send_queued().unwrap();
+        assert_eq!(sent(&mut socket_rx), ["button"]);
+
+        tokio::time::sleep(INPUT_SLOT_DURATION * 2).await;
+        scheduler.release_expired_slots();
+        scheduler.send_queued().unwrap();
+        assert_eq!(sent(&mut socket_rx), ["playbacks"]);
+
+        tokio::time::sleep(INPUT_SLOT_DURATION * 2).await;
+        scheduler.release_expired_slots();
+        scheduler.send_queued().unwrap();
+        assert!(sent(&mut socket_rx).is_empty());
+    }
+
+    #[tokio::test]
+    async fn coalesces_queued_requests_with_the_same_key() {
+        let (mut scheduler, mut socket_rx) = scheduler(1);
+        scheduler.enqueue(request("playbacks", Some(ResponseKind::Playbacks)));
+        scheduler.send_queued().unwrap();
+        scheduler.enqueue(fader(0, 1, "fader 1 low"));
+        scheduler.enqueue(fader(0, 2, "fader 2"));
+        scheduler.enqueue(fader(0, 1, "fader 1 high"));
+        assert_eq!(sent(&mut socket_rx), ["playbacks"]);
+
+        scheduler.release_slot(ResponseKind::Playbacks);
+        scheduler.send_queued().unwrap();
+        assert_eq!(sent(&mut socket_rx), ["fader 1 high"]);
+
+        tokio::time::sleep(INPUT_SLOT_DURATION * 2).await;
+        scheduler.release_expired_slots();
+        scheduler.send_queued().unwrap();
+        assert_eq!(sent(&mut socket_rx), ["fader 2"]);
+    }
+}
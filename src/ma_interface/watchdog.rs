@@ -0,0 +1,133 @@
+use crate::config::WatchdogConfig;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::Mutex;
+use tokio::time::{interval, Instant};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ConnectionHealth {
+    Connected,
+    Degraded,
+    Lost,
+}
+
+pub struct LastReceived {
+    pub playbacks: Instant,
+    pub keep_alive: Instant,
+}
+
+impl LastReceived {
+    pub fn new() -> Self {
+        Self {
+            playbacks: Instant::now(),
+            keep_alive: Instant::now(),
+        }
+    }
+}
+
+impl Default for LastReceived {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct Watchdog {
+    last_received: Arc<Mutex<LastReceived>>,
+    health_sender: UnboundedSender<ConnectionHealth>,
+    playbacks_degraded_after: Duration,
+    playbacks_lost_after: Duration,
+    keep_alive_degraded_after: Duration,
+    keep_alive_lost_after: Duration,
+}
+
+impl Watchdog {
+    pub fn new(config: &WatchdogConfig, last_received: Arc<Mutex<LastReceived>>, health_sender: UnboundedSender<ConnectionHealth>) -> Self {
+        Self {
+            last_received,
+            health_sender,
+            playbacks_degraded_after: config.playbacks_degraded_after(),
+            playbacks_lost_after: config.playbacks_lost_after(),
+            keep_alive_degraded_after: config.keep_alive_degraded_after(),
+            keep_alive_lost_after: config.keep_alive_lost_after(),
+        }
+    }
+
+    pub async fn run(self) {
+        let mut interval = interval(Duration::from_millis(100));
+        let mut current_health = None;
+        loop {
+            interval.tick().await;
+            let health = self.check().await;
+            if current_health != Some(health) {
+                current_health = Some(health);
+                if self.health_sender.send(health).is_err() {
+                    break;
+                }
+            }
+        }
+    }
+
+    async fn check(&self) -> ConnectionHealth {
+        let last_received = self.last_received.lock().await;
+        let playbacks_age = last_received.playbacks.elapsed();
+        let keep_alive_age = last_received.keep_alive.elapsed();
+        drop(last_received);
+        self.health(playbacks_age, keep_alive_age)
+    }
+
+    fn health(&self, playbacks_age: Duration, keep_alive_age: Duration) -> ConnectionHealth {
+        if playbacks_age > self.playbacks_lost_after || keep_alive_age > self.keep_alive_lost_after {
+            ConnectionHealth::Lost
+        } else if playbacks_age > self.playbacks_degraded_after || keep_alive_age > self.keep_alive_degraded_after {
+            ConnectionHealth::Degraded
+        } else {
+            ConnectionHealth::Connected
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn watchdog() -> Watchdog {
+        let (health_sender, _) = tokio::sync::mpsc::unbounded_channel();
+        Watchdog::new(&WatchdogConfig::default(), Arc::new(Mutex::new(LastReceived::new())), health_sender)
+    }
+
+    fn millis(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn rates_health_by_the_oldest_reply() {
+        let watchdog = watchdog();
+        assert_eq!(watchdog.health(millis(100), millis(1000)), ConnectionHealth::Connected);
+        assert_eq!(watchdog.health(millis(600), millis(1000)), ConnectionHealth::Degraded);
+        assert_eq!(watchdog.health(millis(100), millis(7000)), ConnectionHealth::Degraded);
+        assert_eq!(watchdog.health(millis(2500), millis(1000)), ConnectionHealth::Lost);
+        assert_eq!(watchdog.health(millis(100), millis(11000)), ConnectionHealth::Lost);
+        assert_eq!(watchdog.health(millis(100), millis(1000)), ConnectionHealth::Connected);
+    }
+
+    #[tokio::test]
+    async fn reports_each_transition_once() {
+        let config = WatchdogConfig {
+            playbacks_degraded_after: Some(200),
+            playbacks_lost_after: Some(400),
+            ..WatchdogConfig::default()
+        };
+        let (health_sender, mut health_receiver) = tokio::sync::mpsc::unbounded_channel();
+        let last_received = Arc::new(Mutex::new(LastReceived::new()));
+        let task = tokio::spawn(Watchdog::new(&config, last_received.clone(), health_sender).run());
+
+        assert_eq!(health_receiver.recv().await, Some(ConnectionHealth::Connected));
+        assert_eq!(health_receiver.recv().await, Some(ConnectionHealth::Degraded));
+        assert_eq!(health_receiver.recv().await, Some(ConnectionHealth::Lost));
+        *last_received.lock().await = LastReceived::new();
+        assert_eq!(health_receiver.recv().await, Some(ConnectionHealth::Connected));
+        assert!(health_receiver.try_recv().is_err());
+        task.abort();
+    }
+}
@@ -1,22 +1,15 @@
 extern crate core;
 
-mod config;
-mod ma_interface;
-mod midi_controller;
-mod periodic_update_sender;
-
-use crate::ma_interface::{FaderValue, LoginCredentials, Update};
-use config::Config;
-use ma_interface::MaInterface;
-use midi_controller::MidiController;
+use midi_ma::bridge::{distribute_updates, main_loop};
+use midi_ma::config::Config;
+use midi_ma::ma_interface::endpoint::Endpoint;
+use midi_ma::ma_interface::{LoginCredentials, Update};
+use midi_ma::midi_controller::MidiController;
+use midi_ma::mirror::MirrorConsole;
 use std::error::Error;
+use std::path::PathBuf;
 use std::sync::Arc;
-use std::time::Duration;
-use tokio::sync::mpsc::{UnboundedReceiver};
-use tokio::sync::Mutex;
-use tokio::time::Instant;
-
-use url::Url;
+use tokio::sync::mpsc::UnboundedSender;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
@@ -28,58 +21,64 @@ async fn main() -> Result<(), Box<dyn Error>> {
     };
     println!("Read config file");
 
-    let (update_sender, update_receiver) = tokio::sync::mpsc::unbounded_channel();
+    let console_addresses = config.console_addresses();
+    if console_addresses.is_empty() {
+        return Err("No console address configured. Set console_address or consoles in midiMA.json".into());
+    }
+    let mut endpoints = console_addresses.iter().map(Endpoint::from_address).collect::<Result<Vec<Endpoint>, _>>()?;
+    for endpoint in &mut endpoints {
+        endpoint.capture_file = config.capture_file.as_ref().map(PathBuf::from);
+        endpoint.replay_file = config.replay_file.as_ref().map(PathBuf::from);
+    }
 
-    let midi_controller = MidiController::new(config.midi_devices.clone(), update_sender)?;
+    let mirror_endpoints = config.mirror_consoles.clone().unwrap_or_default().iter().map(Endpoint::from_address).collect::<Result<Vec<Endpoint>, _>>()?;
+    let mut mirrors: Vec<MirrorConsole> = mirror_endpoints
+        .into_iter()
+        .enumerate()
+        .map(|(index, endpoint)| MirrorConsole::start(format!("mirror console {}", index + 1), endpoint, login_credentials.clone(), config.max_requests.unwrap_or(10), config.reconnect.clone().unwrap_or_default()))
+        .collect();
+    let mirror_senders: Vec<UnboundedSender<Update>> = mirrors.iter().map(MirrorConsole::sender).collect();
 
+    let (update_sender, mut update_receiver) = tokio::sync::mpsc::unbounded_channel();
 
-    main_loop(config, login_credentials, midi_controller, update_receiver).await
-}
+    let midi_controller = MidiController::new(config.midi_devices.clone(), update_sender)?;
 
-async fn main_loop(config: Arc<Config>, login_credentials: LoginCredentials, mut midi_controller: MidiController, update_receiver: UnboundedReceiver<Update>) -> Result<(), Box<dyn Error>> {
-    let exec_value_receiver_mutex = Arc::new(Mutex::new(update_receiver));
-    loop {
-        let url = Url::parse(&("ws://".to_string() + &config.console_ip))?;
-        let ma_mutex = Arc::new(Mutex::new(MaInterface::new(&url, &login_credentials).await?));
-        println!("Connected to MA2 at {:?}", url.to_string());
-        let forward_task = tokio::spawn(fader_to_ma_forward_loop(ma_mutex.clone(), exec_value_receiver_mutex.clone()));
+    if !mirror_senders.is_empty() {
+        let (feedback_console_sender, feedback_console_receiver) = tokio::sync::mpsc::unbounded_channel();
+        tokio::spawn(distribute_updates(update_receiver, feedback_console_sender, mirror_senders.clone()));
+        update_receiver = feedback_console_receiver;
+    }
 
-        let last_message_received_instant = Arc::new(Mutex::new(Instant::now()));
+    let (shutdown_sender, shutdown_receiver) = tokio::sync::mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        wait_for_shutdown_signal().await;
+        let _ = shutdown_sender.send(());
+    });
 
-        ma_poll_loop(config.ma_poll_interval, ma_mutex.clone(), &mut midi_controller, last_message_received_instant).await;
-        forward_task.abort();
-        println!("Network fail. Trying to reconnect...");
+    let result = main_loop(config, endpoints, login_credentials, midi_controller, update_receiver, &mirror_senders, shutdown_receiver).await;
+    //Mirrors are shut down last, they receive the updates drained from the midi devices as well
+    for mirror in &mut mirrors {
+        mirror.shutdown().await;
     }
+    result
 }
 
-async fn ma_poll_loop(poll_interval: u64, ma_mutex: Arc<Mutex<MaInterface>>, midi_controller: &mut MidiController, last_message_received_instant: Arc<Mutex<Instant>>) {
-    let mut interval = tokio::time::interval(Duration::from_millis(poll_interval));
-    loop {
-        interval.tick().await;
-
-        let mut ma_lock = ma_mutex.lock().await;
-        let timeout_result = tokio::time::timeout(Duration::from_millis(2000), ma_lock.poll_fader_values()).await;
-        drop(ma_lock);
-        if let Ok(result) = timeout_result {
-            if let Ok(values) = result {
-                *last_message_received_instant.lock().await = Instant::now();
-                for (i,value) in values.iter().enumerate() {
-                    midi_controller.receive_update_from_ma(Update::FaderUpdate(FaderValue{
-                        fader_value: *value,
-                        exec_index: i as u8
-                    })).await;
-                }
-            }
-        } else {
-            break;
+async fn wait_for_shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        let Ok(mut terminate) = signal(SignalKind::terminate()) else {
+            let _ = tokio::signal::ctrl_c().await;
+            return;
+        };
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = terminate.recv() => {}
         }
     }
-}
-
-async fn fader_to_ma_forward_loop(ma: Arc<Mutex<MaInterface>>, exec_value_receiver_mutex: Arc<Mutex<UnboundedReceiver<Update>>>) {
-    let mut exec_value_receiver = exec_value_receiver_mutex.lock().await;
-    while let Some(value) = exec_value_receiver.recv().await {
-        ma.lock().await.send_update(value).unwrap();
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
     }
 }
 
@@ -95,5 +94,7 @@ fn get_config() -> Result<Config, Box<dyn Error>> {
             Config::default()
         }
     };
+    //A config that can't work is reported instead of being replaced by the default one
+    config.validate()?;
     Ok(config)
 }
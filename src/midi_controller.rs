@@ -27,9 +27,21 @@ impl MidiController {
         })
     }
 
+    pub fn from_devices(midi_devices: Vec<MidiDevice>) -> MidiController {
+        MidiController {
+            midi_devices
+        }
+    }
+
     pub async fn receive_update_from_ma(&mut self, update: Update) {
         for device in self.midi_devices.iter_mut() {
-            device.receive_update_from_ma(update).await;
+            device.receive_update_from_ma(update.clone()).await;
+        }
+    }
+
+    pub async fn shutdown(self) {
+        for device in self.midi_devices {
+            device.shutdown().await;
         }
     }
 }
@@ -1,5 +1,6 @@
 use std::error::Error;
 use std::sync::Arc;
+use std::time::Duration;
 
 use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
 use tokio::sync::Mutex;
@@ -13,12 +14,15 @@ use crate::midi_controller::midi_device::feedback_handle::ModelFeedbackHandle;
 use crate::midi_controller::midi_device::model::components::{MidiMessageReceiver, ReceivingError};
 use crate::midi_controller::midi_message::MidiMessage;
 
-mod model;
-mod feedback_handle;
+pub mod model;
+pub mod feedback_handle;
 mod connection;
 
+//How long the goodbye output may take to reach the device
+const GOODBYE_TIMEOUT: Duration = Duration::from_millis(500);
+
 pub struct MidiDevice {
-    _connection: Connection,
+    connection: Option<Connection>,
     midi_input_process_task: JoinHandle<()>,
     model_mutex: Arc<Mutex<DeviceModel>>,
 }
@@ -31,14 +35,21 @@ impl MidiDevice {
         let feedback_handle = ModelFeedbackHandle::new(ma_feedback_handle, channels.sender);
         let model = DeviceModel::new(config.model.clone(), feedback_handle)?;
 
+        let mut device = Self::from_model(model, channels.receiver);
+        device.connection = Some(connection);
+        Ok(device)
+    }
+
+    //A device without midi ports, its midi output goes wherever the feedback handle of the model points to
+    pub fn from_model(model: DeviceModel, midi_inputs: UnboundedReceiver<MidiMessage>) -> Self {
         let model_mutex = Arc::new(Mutex::new(model));
-        let midi_input_process_task = tokio::spawn(Self::process_all_midi_inputs(channels.receiver, model_mutex.clone()));
+        let midi_input_process_task = tokio::spawn(Self::process_all_midi_inputs(midi_inputs, model_mutex.clone()));
 
-        Ok(Self {
-            _connection: connection,
+        Self {
+            connection: None,
             midi_input_process_task,
             model_mutex,
-        })
+        }
     }
 
     pub async fn receive_update_from_ma(&mut self, update: Update) {
@@ -51,6 +62,20 @@ impl MidiDevice {
 
     }
 
+    //Stops reacting to the device and sends the goodbye output. Inputs are stopped first,
+    //so motor faders moving to zero aren't sent to MA2
+    pub async fn shutdown(mut self) {
+        self.midi_input_process_task.abort();
+        let _ = (&mut self.midi_input_process_task).await;
+        if let Err(e) = self.model_mutex.lock().await.say_goodbye().await {
+            println!("Sending goodbye to midi device failed: {:?}", e);
+        }
+        if let Some(connection) = self.connection.take() {
+            drop(self);
+            connection.close(GOODBYE_TIMEOUT).await;
+        }
+    }
+
     async fn process_all_midi_inputs(mut source: UnboundedReceiver<MidiMessage>, model_mutex: Arc<Mutex<DeviceModel>>) {
         while let Some(message) = source.recv().await {
             let mut model = model_mutex.lock().await;
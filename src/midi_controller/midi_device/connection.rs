@@ -1,4 +1,5 @@
 use std::error::Error;
+use std::time::Duration;
 use midir::{MidiInput, MidiInputConnection, MidiIO, MidiOutput, MidiOutputConnection};
 use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
 use tokio::task::JoinHandle;
@@ -48,6 +49,11 @@ impl Connection {
         )
     }
 
+    //Waits until every queued message reached the device. The queue only ends once all senders are dropped
+    pub async fn close(mut self, timeout: Duration) {
+        let _ = tokio::time::timeout(timeout, &mut self.sender_task).await;
+    }
+
     fn find_midi_port<T: MidiIO>(midi: &mut T, port_name: &str) -> Result<T::Port, Box<dyn Error>> {
         for port in midi.ports() {
             if midi.port_name(&port)?.eq_ignore_ascii_case(port_name) {
@@ -1,19 +1,31 @@
 use std::error::Error;
 use async_trait::async_trait;
-use crate::config::{DeviceModelConfig};
+use tokio::sync::mpsc::UnboundedSender;
+use crate::config::{DeviceModelConfig, GoodbyeConfig};
 use crate::ma_interface::Update;
+use crate::ma_interface::watchdog::ConnectionHealth;
 
 use crate::midi_controller::midi_message::MidiMessage;
 use crate::midi_controller::midi_device::model::components::button::Button;
+use crate::midi_controller::midi_device::model::components::command_button::CommandButton;
 use crate::midi_controller::midi_device::model::components::fader::Fader;
+use crate::midi_controller::midi_device::model::components::page_button::PageButton;
+use crate::midi_controller::midi_device::model::components::special_master_control::SpecialMasterControl;
+use crate::midi_controller::midi_device::model::components::status_indicator::{LedDimming, StatusIndicator};
 use crate::midi_controller::midi_device::ModelFeedbackHandle;
-use crate::midi_controller::midi_device::model::components::{MaUpdateReceiver, MidiDeviceComponent, MidiMessageReceiver, ReceivingError, ReceivingState};
+use crate::midi_controller::midi_device::model::components::{LedComponent, MaUpdateReceiver, MidiDeviceComponent, MidiMessageReceiver, ReceivingError, ReceivingState};
 
 pub mod components;
 
 pub struct DeviceModel {
     faders: Vec<Fader>,
     buttons: Vec<Button>,
+    page_buttons: Vec<PageButton>,
+    command_buttons: Vec<CommandButton>,
+    special_masters: Vec<SpecialMasterControl>,
+    status_indicator: Option<StatusIndicator>,
+    goodbye: Option<GoodbyeConfig>,
+    midi: UnboundedSender<MidiMessage>,
 }
 
 impl DeviceModel {
@@ -28,18 +40,104 @@ impl DeviceModel {
             let button = Button::new(button_config, feedback_handle.clone())?;
             buttons.push(button);
         }
+        let mut page_buttons = Vec::with_capacity(config.page_buttons.len());
+        for page_button_config in config.page_buttons {
+            let page_button = PageButton::new(page_button_config, feedback_handle.clone())?;
+            page_buttons.push(page_button);
+        }
+        let mut command_buttons = Vec::with_capacity(config.command_buttons.len());
+        for command_button_config in config.command_buttons {
+            let command_button = CommandButton::new(command_button_config, feedback_handle.clone())?;
+            command_buttons.push(command_button);
+        }
+        let mut special_masters = Vec::with_capacity(config.special_masters.len());
+        for special_master_config in config.special_masters {
+            let special_master = SpecialMasterControl::new(special_master_config, feedback_handle.clone())?;
+            special_masters.push(special_master);
+        }
+        let status_indicator = match config.status_indicator {
+            Some(status_indicator_config) => Some(StatusIndicator::new(status_indicator_config, feedback_handle.clone())?),
+            None => None,
+        };
         Ok(DeviceModel {
             faders,
             buttons,
+            page_buttons,
+            command_buttons,
+            special_masters,
+            status_indicator,
+            goodbye: config.goodbye,
+            midi: feedback_handle.midi,
         })
     }
 
     pub async fn receive_update_from_ma(&mut self, update: Update) -> Result<(), ReceivingError>{
+        if let Update::ConnectionUpdate(health) = update {
+            return self.receive_connection_health(health);
+        }
         for fader in &mut self.faders {
-            fader.receive_update_from_ma(update).await?;
+            fader.receive_update_from_ma(update.clone()).await?;
         }
         for button in &mut self.buttons {
-            button.receive_update_from_ma(update).await?;
+            button.receive_update_from_ma(update.clone()).await?;
+        }
+        for page_button in &mut self.page_buttons {
+            page_button.receive_update_from_ma(update.clone()).await?;
+        }
+        for command_button in &mut self.command_buttons {
+            command_button.receive_update_from_ma(update.clone()).await?;
+        }
+        for special_master in &mut self.special_masters {
+            special_master.receive_update_from_ma(update.clone()).await?;
+        }
+        Ok(())
+    }
+
+    //Leaves the device in the state configured for when midiMA isn't running
+    pub async fn say_goodbye(&mut self) -> Result<(), ReceivingError> {
+        if let Some(status_indicator) = &mut self.status_indicator {
+            status_indicator.stop()?;
+        }
+        let Some(goodbye) = self.goodbye.clone() else {
+            return Ok(());
+        };
+        if goodbye.faders_to_zero.unwrap_or(false) {
+            for fader in &mut self.faders {
+                fader.move_to_zero().await?;
+            }
+        }
+        if goodbye.leds_off.unwrap_or(false) {
+            let off_value = goodbye.led_off_value.unwrap_or(0);
+            let leds = self.buttons.iter().map(|led| led as &dyn LedComponent)
+                .chain(self.page_buttons.iter().map(|led| led as &dyn LedComponent))
+                .chain(self.command_buttons.iter().map(|led| led as &dyn LedComponent));
+            for led in leds {
+                led.dim_led(off_value)?;
+            }
+        }
+        for data in goodbye.messages.unwrap_or_default() {
+            if self.midi.send(MidiMessage { data }).is_err() {
+                return Err(ReceivingError::MidiError);
+            }
+        }
+        Ok(())
+    }
+
+    fn receive_connection_health(&mut self, health: ConnectionHealth) -> Result<(), ReceivingError> {
+        let dimming = match &mut self.status_indicator {
+            Some(status_indicator) => status_indicator.process_health(health)?,
+            None => None,
+        };
+        if let Some(dimming) = dimming {
+            let leds = self.buttons.iter().map(|led| led as &dyn LedComponent)
+                .chain(self.page_buttons.iter().map(|led| led as &dyn LedComponent))
+                .chain(self.command_buttons.iter().map(|led| led as &dyn LedComponent));
+            for led in leds {
+                match dimming {
+                    LedDimming::Dim(value) => led.dim_led(value)?,
+                    LedDimming::Restore => led.send_led_state()?,
+                }
+            }
         }
         Ok(())
     }
@@ -59,6 +157,21 @@ impl MidiMessageReceiver for DeviceModel {
                 return Ok(ReceivingState::Consumed);
             }
         }
+        for page_button in &mut self.page_buttons {
+            if page_button.receive_midi_message(message).await? == ReceivingState::Consumed {
+                return Ok(ReceivingState::Consumed);
+            }
+        }
+        for command_button in &mut self.command_buttons {
+            if command_button.receive_midi_message(message).await? == ReceivingState::Consumed {
+                return Ok(ReceivingState::Consumed);
+            }
+        }
+        for special_master in &mut self.special_masters {
+            if special_master.receive_midi_message(message).await? == ReceivingState::Consumed {
+                return Ok(ReceivingState::Consumed);
+            }
+        }
         Ok(ReceivingState::Pass)
     }
 }
@@ -1,10 +1,16 @@
 pub mod fader;
 pub mod button;
+pub mod page_button;
+pub mod command_button;
+pub mod special_master_control;
+pub mod status_indicator;
 
 use std::error::Error;
 use async_trait::async_trait;
 use crate::midi_controller::midi_device::feedback_handle::ModelFeedbackHandle;
 use crate::midi_controller::midi_message::MidiMessage;
+use crate::midi_controller::midi_pattern::button_pattern::ButtonPattern;
+use crate::midi_controller::midi_pattern::MidiPattern;
 use crate::Update;
 
 pub trait MidiDeviceComponent<T: MidiMessageReceiver = Self> {
@@ -19,7 +25,7 @@ pub enum ReceivingState {
     Pass,
 }
 
-#[derive(PartialEq)]
+#[derive(Debug, PartialEq)]
 pub enum ReceivingError {
     MaError,
     MidiError,
@@ -36,3 +42,25 @@ pub trait MaUpdateReceiver {
     async fn receive_update_from_ma(&mut self, update: Update) -> Result<(), ReceivingError>;
 }
 
+//Buttons of every kind drive their led through their ButtonPattern, only the pattern and the state differ
+pub trait LedComponent {
+    fn led_pattern(&self) -> &ButtonPattern;
+    fn led_state(&self) -> bool;
+    fn feedback_handle(&self) -> &ModelFeedbackHandle;
+
+    fn dim_led(&self, value: u8) -> Result<(), ReceivingError> {
+        self.send_led_message(self.led_pattern().create_output_message_with_value(value))
+    }
+
+    fn send_led_state(&self) -> Result<(), ReceivingError> {
+        self.send_led_message(self.led_pattern().create_output_message_from_state(&self.led_state()))
+    }
+
+    fn send_led_message(&self, message: MidiMessage) -> Result<(), ReceivingError> {
+        let midi_send_result = self.feedback_handle().midi.send(message);
+        if midi_send_result.is_err() {
+            return Err(ReceivingError::MidiError);
+        }
+        Ok(())
+    }
+}
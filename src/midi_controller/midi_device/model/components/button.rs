@@ -3,15 +3,17 @@ use async_trait::async_trait;
 use crate::config::ButtonConfig;
 use crate::ma_interface::{ButtonValue, Update};
 use crate::midi_controller::midi_device::model::{ModelFeedbackHandle, MidiDeviceComponent, MidiMessageReceiver};
-use crate::midi_controller::midi_device::model::components::{MaUpdateReceiver, ReceivingError, ReceivingState};
+use crate::midi_controller::midi_device::model::components::{LedComponent, MaUpdateReceiver, ReceivingError, ReceivingState};
 use crate::midi_controller::midi_message::MidiMessage;
 use crate::midi_controller::midi_pattern::button_pattern::ButtonPattern;
 use crate::midi_controller::midi_pattern::MidiPattern;
-use crate::Update::ButtonUpdate;
+use crate::Update::{ButtonUpdate, PageUpdate, Resync};
+use crate::config::PageChange;
 
 pub struct Button {
     pattern: ButtonPattern,
     current_state: bool,
+    resync_pending: bool,
     feedback_handle: ModelFeedbackHandle,
     config: ButtonConfig,
 }
@@ -20,25 +22,23 @@ impl Button {
     fn process_midi_input(&mut self, state: bool) -> Result<(), ReceivingError> {
         if self.current_state != state {
             self.current_state = state;
-            self.send_state_to_midi()?;
+            self.send_led_state()?;
             self.send_state_to_ma()?;
         }
         Ok(())
     }
     fn process_ma_input(&mut self, state: bool) -> Result<(), ReceivingError>{
-        if self.current_state != state {
+        if self.current_state != state || self.resync_pending {
             self.current_state = state;
-            self.send_state_to_midi()?;
+            self.resync_pending = false;
+            self.send_led_state()?;
         }
         Ok(())
     }
 
-    fn send_state_to_midi(&self) -> Result<(), ReceivingError>{
-        let midi_send_result = self.feedback_handle.midi.send(self.pattern.create_output_message_from_state(&self.current_state));
-        if midi_send_result.is_err() {
-            return Err(ReceivingError::MidiError);
-        }
-        Ok(())
+    fn process_page_change(&mut self) -> Result<(), ReceivingError> {
+        self.current_state = false;
+        self.send_led_state()
     }
 
     fn send_state_to_ma(&self) -> Result<(), ReceivingError> {
@@ -59,12 +59,27 @@ impl Button {
 }
 
 
+impl LedComponent for Button {
+    fn led_pattern(&self) -> &ButtonPattern {
+        &self.pattern
+    }
+
+    fn led_state(&self) -> bool {
+        self.current_state
+    }
+
+    fn feedback_handle(&self) -> &ModelFeedbackHandle {
+        &self.feedback_handle
+    }
+}
+
 impl MidiDeviceComponent for Button {
     type Config = ButtonConfig;
     fn new(config: Self::Config, feedback_handle: ModelFeedbackHandle) -> Result<Self, Box<dyn Error>> {
         Ok(Self {
-            pattern: ButtonPattern::new(config.clone()),
+            pattern: ButtonPattern::from(&config.midi),
             current_state: false,
+            resync_pending: true,
             feedback_handle,
             config,
         })
@@ -91,6 +106,10 @@ impl MaUpdateReceiver for Button {
             if button_value.exec_index == self.config.ma_executor_index && button_value.position == self.config.position {
                 self.process_ma_input(button_value.button_value)?;
             }
+        } else if let PageUpdate(PageChange::Goto(_)) = update {
+            self.process_page_change()?;
+        } else if let Resync = update {
+            self.resync_pending = true;
         }
         Ok(())
     }
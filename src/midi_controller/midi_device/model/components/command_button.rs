@@ -0,0 +1,93 @@
+use std::error::Error;
+use async_trait::async_trait;
+use crate::config::{CommandButtonConfig, PageChange};
+use crate::ma_interface::Update;
+use crate::midi_controller::midi_device::model::{ModelFeedbackHandle, MidiDeviceComponent, MidiMessageReceiver};
+use crate::midi_controller::midi_device::model::components::{LedComponent, MaUpdateReceiver, ReceivingError, ReceivingState};
+use crate::midi_controller::midi_message::MidiMessage;
+use crate::midi_controller::midi_pattern::button_pattern::ButtonPattern;
+use crate::midi_controller::midi_pattern::MidiPattern;
+use crate::Update::PageUpdate;
+
+pub struct CommandButton {
+    pattern: ButtonPattern,
+    current_state: bool,
+    page_index: u32,
+    feedback_handle: ModelFeedbackHandle,
+    config: CommandButtonConfig,
+}
+
+impl CommandButton {
+    fn process_midi_input(&mut self, state: bool, value: u8) -> Result<(), ReceivingError> {
+        if self.current_state != state {
+            self.current_state = state;
+            self.send_led_state()?;
+            let command = if state { Some(&self.config.press_command) } else { self.config.release_command.as_ref() };
+            if let Some(command) = command {
+                self.send_command_to_ma(self.resolve_placeholders(command, value))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn resolve_placeholders(&self, command: &str, value: u8) -> String {
+        command.replace("{page}", &(self.page_index + 1).to_string()).replace("{value}", &value.to_string())
+    }
+
+    fn send_command_to_ma(&self, command: String) -> Result<(), ReceivingError> {
+        let ma_send_result = self.feedback_handle.ma.send(Update::CommandUpdate(command));
+        if ma_send_result.is_err() {
+            return Err(ReceivingError::MaError);
+        }
+        Ok(())
+    }
+}
+
+impl LedComponent for CommandButton {
+    fn led_pattern(&self) -> &ButtonPattern {
+        &self.pattern
+    }
+
+    fn led_state(&self) -> bool {
+        self.current_state
+    }
+
+    fn feedback_handle(&self) -> &ModelFeedbackHandle {
+        &self.feedback_handle
+    }
+}
+
+impl MidiDeviceComponent for CommandButton {
+    type Config = CommandButtonConfig;
+    fn new(config: Self::Config, feedback_handle: ModelFeedbackHandle) -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            pattern: ButtonPattern::from(&config.midi),
+            current_state: false,
+            page_index: 0,
+            feedback_handle,
+            config,
+        })
+    }
+}
+
+#[async_trait]
+impl MidiMessageReceiver for CommandButton {
+    async fn receive_midi_message(&mut self, message: MidiMessage) -> Result<ReceivingState, ReceivingError> {
+        if let Ok(state) = self.pattern.resolve_value_from_input(&message) {
+            self.process_midi_input(state, message.data[2])?;
+            Ok(ReceivingState::Consumed)
+        } else {
+            Ok(ReceivingState::Pass)
+        }
+    }
+}
+
+#[async_trait]
+impl MaUpdateReceiver for CommandButton {
+    async fn receive_update_from_ma(&mut self, update: Update) -> Result<(), ReceivingError> {
+        if let PageUpdate(PageChange::Goto(page_index)) = update {
+            self.page_index = page_index;
+        }
+        Ok(())
+    }
+}
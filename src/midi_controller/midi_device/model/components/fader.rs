@@ -1,10 +1,12 @@
 use std::error::Error;
 use std::time::Duration;
 use async_trait::async_trait;
-use crate::config::MotorFaderConfig;
+use tokio::sync::mpsc::UnboundedSender;
+use crate::config::{MotorFaderConfig, PageChange};
 use crate::FaderValue;
+use crate::ma_interface::FaderRange;
 use crate::ma_interface::Update;
-use crate::ma_interface::Update::FaderUpdate;
+use crate::ma_interface::Update::{FaderUpdate, PageUpdate, Resync};
 use crate::midi_controller::midi_message::MidiMessage;
 use crate::midi_controller::midi_pattern::fader_pattern::FaderPattern;
 use crate::midi_controller::midi_pattern::MidiPattern;
@@ -17,16 +19,33 @@ pub struct Fader {
     config: MotorFaderConfig,
     pattern: FaderPattern,
     current_state: u8,
+    resync_pending: bool,
+    ma_range: FaderRange,
     ma_update_sender: PeriodicUpdateSender<Update>,
     midi_update_sender: PeriodicUpdateSender<MidiMessage>,
+    midi: UnboundedSender<MidiMessage>,
 }
 
 impl Fader {
     fn fader_value_to_ma_value(&self, v: u8) -> f32 {
-        ((v - self.config.min_value.unwrap_or(0)) as f32) / (self.config.max_value.unwrap_or(127) as f32)
+        let min_value = self.config.min_value.unwrap_or(0) as f32;
+        let max_value = self.config.max_value.unwrap_or(127) as f32;
+        let normalized = if max_value > min_value {
+            ((v as f32 - min_value) / (max_value - min_value)).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        self.ma_range.min + normalized * (self.ma_range.max - self.ma_range.min)
     }
     fn ma_value_to_fader_value(&self, v: f32) -> u8 {
-        (v * (self.config.max_value.unwrap_or(127) as f32)).round() as u8 + self.config.min_value.unwrap_or(0)
+        let min_value = self.config.min_value.unwrap_or(0) as f32;
+        let max_value = self.config.max_value.unwrap_or(127) as f32;
+        let normalized = if self.ma_range.max > self.ma_range.min {
+            ((v - self.ma_range.min) / (self.ma_range.max - self.ma_range.min)).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        (min_value + normalized * (max_value - min_value)).round() as u8
     }
 
     async fn process_midi_input(&mut self, state: u8)  -> Result<(), ReceivingError>{
@@ -39,8 +58,9 @@ impl Fader {
     }
 
     async fn process_ma_input(&mut self, value: u8)  -> Result<(), ReceivingError>{
-        if self.current_state != value {
+        if self.current_state != value || self.resync_pending {
             self.current_state = value;
+            self.resync_pending = false;
             self.send_state_to_midi().await?;
         }
         Ok(())
@@ -62,10 +82,21 @@ impl Fader {
         Ok(())
     }
 
+    //Moves the motor fader to its lowest position without telling MA2
+    pub async fn move_to_zero(&mut self) -> Result<(), ReceivingError> {
+        self.midi_update_sender.cancel().await;
+        self.current_state = self.config.min_value.unwrap_or(0);
+        if self.midi.send(self.pattern.create_output_message_from_state(&self.current_state)).is_err() {
+            return Err(ReceivingError::MidiError);
+        }
+        Ok(())
+    }
+
     fn get_update(&self) -> Update {
         Update::FaderUpdate(FaderValue {
             exec_index: self.config.ma_executor_index,
             fader_value: self.fader_value_to_ma_value(self.current_state),
+            range: None,
         })
     }
 }
@@ -74,13 +105,16 @@ impl MidiDeviceComponent for Fader {
     type Config = MotorFaderConfig;
     fn new(config: Self::Config, feedback_handle: ModelFeedbackHandle) -> Result<Self, Box<dyn Error>> {
         let ma_update_sender = PeriodicUpdateSender::new(feedback_handle.ma, Duration::from_millis(50))?;
-        let midi_update_sender = PeriodicUpdateSender::new(feedback_handle.midi, Duration::from_millis(50))?;
+        let midi_update_sender = PeriodicUpdateSender::new(feedback_handle.midi.clone(), Duration::from_millis(50))?;
         Ok(Self {
-            pattern: FaderPattern::new(config.clone()),
+            pattern: FaderPattern::from(&config),
             current_state: 0,
+            resync_pending: true,
+            ma_range: FaderRange::default(),
             config,
             ma_update_sender,
             midi_update_sender,
+            midi: feedback_handle.midi,
         })
     }
 }
@@ -89,10 +123,18 @@ impl MidiDeviceComponent for Fader {
 impl MaUpdateReceiver for Fader {
     async fn receive_update_from_ma(&mut self, update: Update) -> Result<(), ReceivingError> {
         if let FaderUpdate(value) = update {
-            if value.exec_index == self.config.ma_executor_index && !self.ma_update_sender.is_sending() {
+            if value.exec_index != self.config.ma_executor_index {
+                return Ok(());
+            }
+            if let Some(range) = value.range {
+                self.ma_range = range;
+            }
+            if !self.ma_update_sender.is_sending() {
                 let midi_value = self.ma_value_to_fader_value(value.fader_value);
                 self.process_ma_input(midi_value).await?;
             }
+        } else if let PageUpdate(PageChange::Goto(_)) | Resync = update {
+            self.resync_pending = true;
         }
         Ok(())
     }
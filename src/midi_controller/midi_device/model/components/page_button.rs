@@ -0,0 +1,97 @@
+use std::error::Error;
+use async_trait::async_trait;
+use crate::config::{PageButtonConfig, PageChange};
+use crate::ma_interface::Update;
+use crate::midi_controller::midi_device::model::{ModelFeedbackHandle, MidiDeviceComponent, MidiMessageReceiver};
+use crate::midi_controller::midi_device::model::components::{LedComponent, MaUpdateReceiver, ReceivingError, ReceivingState};
+use crate::midi_controller::midi_message::MidiMessage;
+use crate::midi_controller::midi_pattern::button_pattern::ButtonPattern;
+use crate::midi_controller::midi_pattern::MidiPattern;
+use crate::Update::PageUpdate;
+
+pub struct PageButton {
+    pattern: ButtonPattern,
+    current_state: bool,
+    feedback_handle: ModelFeedbackHandle,
+    config: PageButtonConfig,
+}
+
+impl PageButton {
+    fn process_midi_input(&mut self, state: bool) -> Result<(), ReceivingError> {
+        if state {
+            self.send_page_change_to_ma()?;
+        }
+        if let PageChange::Goto(_) = self.config.action {
+            return Ok(());
+        }
+        if self.current_state != state {
+            self.current_state = state;
+            self.send_led_state()?;
+        }
+        Ok(())
+    }
+
+    fn process_ma_page(&mut self, page_index: u32) -> Result<(), ReceivingError> {
+        if let PageChange::Goto(target_page_index) = self.config.action {
+            self.current_state = target_page_index == page_index;
+            self.send_led_state()?;
+        }
+        Ok(())
+    }
+
+    fn send_page_change_to_ma(&self) -> Result<(), ReceivingError> {
+        let ma_send_result = self.feedback_handle.ma.send(Update::PageUpdate(self.config.action));
+        if ma_send_result.is_err() {
+            return Err(ReceivingError::MaError);
+        }
+        Ok(())
+    }
+}
+
+impl LedComponent for PageButton {
+    fn led_pattern(&self) -> &ButtonPattern {
+        &self.pattern
+    }
+
+    fn led_state(&self) -> bool {
+        self.current_state
+    }
+
+    fn feedback_handle(&self) -> &ModelFeedbackHandle {
+        &self.feedback_handle
+    }
+}
+
+impl MidiDeviceComponent for PageButton {
+    type Config = PageButtonConfig;
+    fn new(config: Self::Config, feedback_handle: ModelFeedbackHandle) -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            pattern: ButtonPattern::from(&config.midi),
+            current_state: false,
+            feedback_handle,
+            config,
+        })
+    }
+}
+
+#[async_trait]
+impl MidiMessageReceiver for PageButton {
+    async fn receive_midi_message(&mut self, message: MidiMessage) -> Result<ReceivingState, ReceivingError> {
+        if let Ok(value) = self.pattern.resolve_value_from_input(&message) {
+            self.process_midi_input(value)?;
+            Ok(ReceivingState::Consumed)
+        } else {
+            Ok(ReceivingState::Pass)
+        }
+    }
+}
+
+#[async_trait]
+impl MaUpdateReceiver for PageButton {
+    async fn receive_update_from_ma(&mut self, update: Update) -> Result<(), ReceivingError> {
+        if let PageUpdate(PageChange::Goto(page_index)) = update {
+            self.process_ma_page(page_index)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,124 @@
+use std::error::Error;
+use std::time::Duration;
+use async_trait::async_trait;
+use crate::config::{ControlInputMode, SpecialMasterConfig};
+use crate::ma_interface::{SpecialMasterValue, Update};
+use crate::midi_controller::midi_message::MidiMessage;
+use crate::midi_controller::midi_pattern::fader_pattern::FaderPattern;
+use crate::midi_controller::midi_pattern::MidiPattern;
+use crate::midi_controller::midi_device::model::{ModelFeedbackHandle, MidiMessageReceiver};
+use crate::midi_controller::midi_device::model::components::{MaUpdateReceiver, MidiDeviceComponent, ReceivingError, ReceivingState};
+use crate::periodic_update_sender::PeriodicUpdateSender;
+
+//WebRemote does not report special masters, so the state is only known locally
+pub struct SpecialMasterControl {
+    config: SpecialMasterConfig,
+    pattern: FaderPattern,
+    current_value: f32,
+    ma_update_sender: PeriodicUpdateSender<Update>,
+    midi_update_sender: PeriodicUpdateSender<MidiMessage>,
+}
+
+impl SpecialMasterControl {
+    fn midi_value_to_master_value(&self, v: u8) -> f32 {
+        let min_value = self.config.min_value.unwrap_or(0) as f32;
+        let max_value = self.config.max_value.unwrap_or(127) as f32;
+        let (master_min, master_max) = self.config.master_range();
+        let normalized = if max_value > min_value {
+            ((v as f32 - min_value) / (max_value - min_value)).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        master_min + normalized * (master_max - master_min)
+    }
+
+    fn master_value_to_midi_value(&self, v: f32) -> u8 {
+        let min_value = self.config.min_value.unwrap_or(0) as f32;
+        let max_value = self.config.max_value.unwrap_or(127) as f32;
+        let (master_min, master_max) = self.config.master_range();
+        let normalized = if master_max > master_min {
+            ((v - master_min) / (master_max - master_min)).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        (min_value + normalized * (max_value - min_value)).round() as u8
+    }
+
+    //Relative encoders send increments as 1..63 and decrements as 127..65
+    fn relative_value_to_master_value(&self, v: u8) -> f32 {
+        let ticks = if v < 64 { v as f32 } else { v as f32 - 128.0 };
+        let (master_min, master_max) = self.config.master_range();
+        (self.current_value + ticks * self.config.relative_step.unwrap_or(1.0)).clamp(master_min, master_max)
+    }
+
+    async fn process_midi_input(&mut self, v: u8) -> Result<(), ReceivingError> {
+        let value = match self.config.input_mode.unwrap_or(ControlInputMode::Absolute) {
+            ControlInputMode::Absolute => self.midi_value_to_master_value(v),
+            ControlInputMode::Relative => self.relative_value_to_master_value(v),
+        };
+        if self.current_value != value {
+            self.current_value = value;
+            self.send_state_to_ma().await?;
+            self.send_state_to_midi().await?;
+        }
+        Ok(())
+    }
+
+    async fn send_state_to_midi(&mut self) -> Result<(), ReceivingError> {
+        let midi_value = self.master_value_to_midi_value(self.current_value);
+        let midi_send_result = self.midi_update_sender.set_value(self.pattern.create_output_message_from_state(&midi_value)).await;
+        if midi_send_result.is_err() {
+            return Err(ReceivingError::MidiError);
+        }
+        Ok(())
+    }
+
+    async fn send_state_to_ma(&mut self) -> Result<(), ReceivingError> {
+        let ma_send_result = self.ma_update_sender.set_value(self.get_update()).await;
+        if ma_send_result.is_err() {
+            return Err(ReceivingError::MaError);
+        }
+        Ok(())
+    }
+
+    fn get_update(&self) -> Update {
+        Update::SpecialMasterUpdate(SpecialMasterValue {
+            master: self.config.master,
+            value: self.current_value,
+        })
+    }
+}
+
+impl MidiDeviceComponent for SpecialMasterControl {
+    type Config = SpecialMasterConfig;
+    fn new(config: Self::Config, feedback_handle: ModelFeedbackHandle) -> Result<Self, Box<dyn Error>> {
+        let ma_update_sender = PeriodicUpdateSender::new(feedback_handle.ma, Duration::from_millis(50))?;
+        let midi_update_sender = PeriodicUpdateSender::new(feedback_handle.midi, Duration::from_millis(50))?;
+        Ok(Self {
+            pattern: FaderPattern::from(&config),
+            current_value: 0.0,
+            config,
+            ma_update_sender,
+            midi_update_sender,
+        })
+    }
+}
+
+#[async_trait]
+impl MaUpdateReceiver for SpecialMasterControl {
+    async fn receive_update_from_ma(&mut self, _update: Update) -> Result<(), ReceivingError> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl MidiMessageReceiver for SpecialMasterControl {
+    async fn receive_midi_message(&mut self, message: MidiMessage) -> Result<ReceivingState, ReceivingError> {
+        if let Ok(value) = self.pattern.resolve_value_from_input(&message) {
+            self.process_midi_input(value).await?;
+            Ok(ReceivingState::Consumed)
+        } else {
+            Ok(ReceivingState::Pass)
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use std::error::Error;
+use std::time::Duration;
+use async_trait::async_trait;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::task::JoinHandle;
+use tokio::time::interval;
+use crate::config::{StatusIndication, StatusIndicatorConfig};
+use crate::ma_interface::watchdog::ConnectionHealth;
+use crate::midi_controller::midi_device::model::{ModelFeedbackHandle, MidiDeviceComponent, MidiMessageReceiver};
+use crate::midi_controller::midi_device::model::components::{ReceivingError, ReceivingState};
+use crate::midi_controller::midi_message::MidiMessage;
+
+pub enum LedDimming {
+    Dim(u8),
+    Restore,
+}
+
+pub struct StatusIndicator {
+    config: StatusIndicatorConfig,
+    midi: UnboundedSender<MidiMessage>,
+    indicating: bool,
+    blink_task: Option<JoinHandle<()>>,
+}
+
+impl StatusIndicator {
+    //Returns how the other leds of the device have to change for the new connection health
+    pub fn process_health(&mut self, health: ConnectionHealth) -> Result<Option<LedDimming>, ReceivingError> {
+        let indicating = match health {
+            ConnectionHealth::Connected => false,
+            ConnectionHealth::Degraded => self.config.indicate_degraded.unwrap_or(false),
+            ConnectionHealth::Lost => true,
+        };
+        if self.indicating == indicating {
+            return Ok(None);
+        }
+        self.indicating = indicating;
+        match self.config.indication {
+            StatusIndication::BlinkLed { output_midi_byte_0, output_midi_byte_1, on_value, off_value, period } => {
+                if let Some(blink_task) = self.blink_task.take() {
+                    blink_task.abort();
+                }
+                let off_message = MidiMessage {
+                    data: [output_midi_byte_0, output_midi_byte_1, off_value.unwrap_or(0)],
+                };
+                if indicating {
+                    let on_message = MidiMessage {
+                        data: [output_midi_byte_0, output_midi_byte_1, on_value.unwrap_or(127)],
+                    };
+                    self.blink_task = Some(tokio::spawn(Self::blink_loop(self.midi.clone(), on_message, off_message, Duration::from_millis(period.unwrap_or(500)))));
+                } else if self.midi.send(off_message).is_err() {
+                    return Err(ReceivingError::MidiError);
+                }
+                Ok(None)
+            }
+            StatusIndication::DimLeds { dim_value } => {
+                if indicating {
+                    Ok(Some(LedDimming::Dim(dim_value)))
+                } else {
+                    Ok(Some(LedDimming::Restore))
+                }
+            }
+        }
+    }
+
+    //Stops blinking, so the led doesn't stay lit when the device is left alone
+    pub fn stop(&mut self) -> Result<(), ReceivingError> {
+        let Some(blink_task) = self.blink_task.take() else {
+            return Ok(());
+        };
+        blink_task.abort();
+        if let StatusIndication::BlinkLed { output_midi_byte_0, output_midi_byte_1, off_value, .. } = self.config.indication {
+            let off_message = MidiMessage {
+                data: [output_midi_byte_0, output_midi_byte_1, off_value.unwrap_or(0)],
+            };
+            if self.midi.send(off_message).is_err() {
+                return Err(ReceivingError::MidiError);
+            }
+        }
+        Ok(())
+    }
+
+    async fn blink_loop(midi: UnboundedSender<MidiMessage>, on_message: MidiMessage, off_message: MidiMessage, period: Duration) {
+        let mut interval = interval(period);
+        let mut on = false;
+        loop {
+            interval.tick().await;
+            on = !on;
+            if midi.send(if on { on_message } else { off_message }).is_err() {
+                break;
+            }
+        }
+    }
+}
+
+impl MidiDeviceComponent for StatusIndicator {
+    type Config = StatusIndicatorConfig;
+    fn new(config: Self::Config, feedback_handle: ModelFeedbackHandle) -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            config,
+            midi: feedback_handle.midi,
+            indicating: false,
+            blink_task: None,
+        })
+    }
+}
+
+#[async_trait]
+impl MidiMessageReceiver for StatusIndicator {
+    async fn receive_midi_message(&mut self, _message: MidiMessage) -> Result<ReceivingState, ReceivingError> {
+        Ok(ReceivingState::Pass)
+    }
+}
+
+impl Drop for StatusIndicator {
+    fn drop(&mut self) {
+        if let Some(blink_task) = self.blink_task.take() {
+            blink_task.abort();
+        }
+    }
+}
@@ -5,6 +5,7 @@ use crate::midi_controller::midi_message::MidiMessage;
 
 pub trait MidiPattern {
     type State;
+    #[allow(clippy::result_unit_err)]
     fn resolve_value_from_input(&self, message: &MidiMessage) -> Result<Self::State, ()>;
     fn create_output_message_from_state(&self, state: &Self::State) -> MidiMessage;
 }
\ No newline at end of file
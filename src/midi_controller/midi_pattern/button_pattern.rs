@@ -1,15 +1,29 @@
-use crate::config::ButtonConfig;
+use crate::config::ButtonMidiConfig;
 use crate::midi_controller::midi_message::MidiMessage;
 use crate::midi_controller::midi_pattern::MidiPattern;
 
 pub struct ButtonPattern {
-    config: ButtonConfig,
+    input_midi_bytes: [u8; 2],
+    output_midi_bytes: [u8; 2],
+    low_value: Option<u8>,
+    high_value: Option<u8>,
 }
 
-impl ButtonPattern {
-    pub fn new(config: ButtonConfig) -> Self {
+impl From<&ButtonMidiConfig> for ButtonPattern {
+    fn from(config: &ButtonMidiConfig) -> Self {
         Self {
-            config
+            input_midi_bytes: [config.input_midi_byte_0, config.input_midi_byte_1],
+            output_midi_bytes: [config.output_midi_byte_0, config.output_midi_byte_1],
+            low_value: config.low_value,
+            high_value: config.high_value,
+        }
+    }
+}
+
+impl ButtonPattern {
+    pub fn create_output_message_with_value(&self, value: u8) -> MidiMessage {
+        MidiMessage {
+            data: [self.output_midi_bytes[0], self.output_midi_bytes[1], value]
         }
     }
 }
@@ -17,8 +31,8 @@ impl ButtonPattern {
 impl MidiPattern for ButtonPattern {
     type State = bool;
     fn resolve_value_from_input(&self, message: &MidiMessage) -> Result<Self::State, ()> {
-        if self.config.input_midi_byte_0 == message.data[0] && self.config.input_midi_byte_1 == message.data[1] {
-            if message.data[2] == self.config.low_value.unwrap_or(0) {
+        if self.input_midi_bytes[0] == message.data[0] && self.input_midi_bytes[1] == message.data[1] {
+            if message.data[2] == self.low_value.unwrap_or(0) {
                 Ok(false)
             } else {
                 Ok(true)
@@ -30,13 +44,13 @@ impl MidiPattern for ButtonPattern {
     fn create_output_message_from_state(&self, value: &Self::State) -> MidiMessage {
         let val = {
             if *value {
-                self.config.high_value.unwrap_or(127)
+                self.high_value.unwrap_or(127)
             } else {
-                self.config.low_value.unwrap_or(0)
+                self.low_value.unwrap_or(0)
             }
         };
         MidiMessage {
-            data: [self.config.output_midi_byte_0, self.config.output_midi_byte_1, val]
+            data: [self.output_midi_bytes[0], self.output_midi_bytes[1], val]
         }
     }
-}
\ No newline at end of file
+}
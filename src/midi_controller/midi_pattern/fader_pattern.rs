@@ -1,14 +1,27 @@
-use crate::config::MotorFaderConfig;
+use crate::config::{MotorFaderConfig, SpecialMasterConfig};
 use crate::midi_controller::midi_message::MidiMessage;
 use crate::midi_controller::midi_pattern::MidiPattern;
 
 pub struct FaderPattern {
-    config: MotorFaderConfig,
+    input_midi_bytes: [u8; 2],
+    output_midi_bytes: [u8; 2],
 }
 
-impl FaderPattern {
-    pub fn new(config: MotorFaderConfig) -> Self {
-        Self { config }
+impl From<&MotorFaderConfig> for FaderPattern {
+    fn from(config: &MotorFaderConfig) -> Self {
+        Self {
+            input_midi_bytes: [config.input_midi_byte_0, config.input_midi_byte_1],
+            output_midi_bytes: [config.output_midi_byte_0, config.output_midi_byte_1],
+        }
+    }
+}
+
+impl From<&SpecialMasterConfig> for FaderPattern {
+    fn from(config: &SpecialMasterConfig) -> Self {
+        Self {
+            input_midi_bytes: [config.input_midi_byte_0, config.input_midi_byte_1],
+            output_midi_bytes: [config.output_midi_byte_0, config.output_midi_byte_1],
+        }
     }
 }
 
@@ -16,7 +29,7 @@ impl MidiPattern for FaderPattern {
     type State = u8;
 
     fn resolve_value_from_input(&self, message: &MidiMessage) -> Result<Self::State, ()> {
-        if message.data[0] == self.config.input_midi_byte_0 && message.data[1] == self.config.input_midi_byte_1 {
+        if message.data[0] == self.input_midi_bytes[0] && message.data[1] == self.input_midi_bytes[1] {
             Ok(message.data[2])
         } else {
             Err(())
@@ -25,7 +38,7 @@ impl MidiPattern for FaderPattern {
 
     fn create_output_message_from_state(&self, value: &Self::State) -> MidiMessage {
         MidiMessage {
-            data: [self.config.output_midi_byte_0, self.config.output_midi_byte_1, *value]
+            data: [self.output_midi_bytes[0], self.output_midi_bytes[1], *value]
         }
     }
 }
\ No newline at end of file
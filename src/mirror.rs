@@ -0,0 +1,132 @@
+use crate::backoff::Backoff;
+use crate::config::ReconnectConfig;
+use crate::ma_interface::endpoint::Endpoint;
+use crate::ma_interface::{LoginCredentials, MaInterface, Update};
+use crate::update_queue::UpdateQueue;
+use std::time::Duration;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+use tokio::task::JoinHandle;
+
+//Mirrors are never used for feedback, but polling one executor keeps their session supervised
+const MIRROR_CHECK_INTERVAL: Duration = Duration::from_millis(1000);
+const MIRROR_SHUTDOWN_TIMEOUT: Duration = Duration::from_millis(3000);
+
+//Console that receives every update sent to the feedback console without being polled for feedback itself.
+//It runs its own session with its own reconnects, so it never holds up the feedback console
+pub struct MirrorConsole {
+    sender: UnboundedSender<Update>,
+    shutdown_sender: UnboundedSender<()>,
+    task: JoinHandle<()>,
+}
+
+impl MirrorConsole {
+    pub fn start(name: String, endpoint: Endpoint, login_credentials: LoginCredentials, max_requests: u32, reconnect: ReconnectConfig) -> Self {
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        let (shutdown_sender, shutdown_receiver) = tokio::sync::mpsc::unbounded_channel();
+        let task = tokio::spawn(Self::mirror_loop(name, endpoint, login_credentials, max_requests, reconnect, receiver, shutdown_receiver));
+        Self { sender, shutdown_sender, task }
+    }
+
+    pub fn sender(&self) -> UnboundedSender<Update> {
+        self.sender.clone()
+    }
+
+    //Sends the updates that are still queued, logs out and waits for the mirror to finish
+    pub async fn shutdown(&mut self) {
+        if self.shutdown_sender.send(()).is_ok() {
+            let _ = tokio::time::timeout(MIRROR_SHUTDOWN_TIMEOUT, &mut self.task).await;
+        }
+    }
+
+    async fn mirror_loop(name: String, endpoint: Endpoint, login_credentials: LoginCredentials, max_requests: u32, reconnect: ReconnectConfig, mut updates: UnboundedReceiver<Update>, mut shutdown: UnboundedReceiver<()>) {
+        let mut backoff = Backoff::new(&reconnect);
+        let mut page_index = 0;
+        loop {
+            let connect_result = tokio::select! {
+                result = MaInterface::new(&endpoint, &login_credentials, max_requests) => result,
+                _ = shutdown.recv() => return,
+            };
+            let mut ma = match connect_result {
+                Ok(ma) => ma,
+                Err(e) => {
+                    let Some(delay) = backoff.next_delay() else {
+                        println!("Giving up on {} at {}: {}", name, endpoint.url, e);
+                        return;
+                    };
+                    println!("Connecting to {} at {} failed: {}. Retrying in {} ms", name, endpoint.url, e, delay.as_millis());
+                    tokio::select! {
+                        _ = tokio::time::sleep(delay) => continue,
+                        _ = shutdown.recv() => return,
+                    }
+                }
+            };
+            ma.set_page_index(page_index);
+            ma.set_executor_ranges(&[]);
+            backoff.reset();
+            //Button presses and commands missed while disconnected are outdated, replaying them could fire them long after
+            //they were triggered. Page changes, fader and master values still apply, the queue keeps the latest value of each
+            let mut queue = UpdateQueue::new();
+            while let Ok(update) = updates.try_recv() {
+                match update {
+                    Update::ButtonUpdate(_) | Update::CommandUpdate(_) => {}
+                    update => queue.push(update),
+                }
+            }
+            println!("Mirroring to {} at {}", name, endpoint.url);
+
+            let mut interval = tokio::time::interval(MIRROR_CHECK_INTERVAL);
+            'session: loop {
+                while let Some(update) = queue.pop() {
+                    if let Err(e) = ma.send_update(update).await {
+                        println!("Failed to send update to {}: {}", name, e);
+                        if e.requires_reconnect() {
+                            break 'session;
+                        }
+                    }
+                }
+                tokio::select! {
+                    update = updates.recv() => {
+                        match update {
+                            Some(update) => queue.push(update),
+                            None => return,
+                        }
+                    }
+                    _ = interval.tick() => {
+                        if let Err(e) = ma.poll_executors().await {
+                            if e.requires_reconnect() {
+                                println!("Connection to {} lost: {}", name, e);
+                                break 'session;
+                            }
+                        }
+                        continue;
+                    }
+                    _ = shutdown.recv() => {
+                        while let Ok(update) = updates.try_recv() {
+                            queue.push(update);
+                        }
+                        while let Some(update) = queue.pop() {
+                            if let Err(e) = ma.send_update(update).await {
+                                println!("Failed to send update to {}: {}", name, e);
+                                break;
+                            }
+                        }
+                        if let Err(e) = ma.close().await {
+                            println!("Logging out of {} failed: {}", name, e);
+                        }
+                        return;
+                    }
+                }
+                while let Ok(update) = updates.try_recv() {
+                    queue.push(update);
+                }
+            }
+            page_index = ma.page_index();
+        }
+    }
+}
+
+impl Drop for MirrorConsole {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
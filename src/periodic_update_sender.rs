@@ -37,7 +37,7 @@ impl<T: Clone + Send + 'static> PeriodicUpdateSender<T> {
         if self.sender_task.is_none() {
             self.start_sender_task();
         } else if self.sender_task.as_ref().unwrap().is_finished() {
-            let task = std::mem::replace(&mut self.sender_task, None).unwrap();
+            let task = self.sender_task.take().unwrap();
             let result = task.await;
             if let Ok(Err(e)) = result {
                 return Err(e.into());
@@ -52,6 +52,14 @@ impl<T: Clone + Send + 'static> PeriodicUpdateSender<T> {
         !(self.sender_task.is_none() || self.sender_task.as_ref().unwrap().is_finished())
     }
 
+    //Drops a value that wasn't sent yet, so it can't overwrite a value sent directly
+    pub async fn cancel(&mut self) {
+        if let Some(task) = self.sender_task.take() {
+            task.abort();
+        }
+        *self.value.lock().await = None;
+    }
+
     fn start_sender_task(&mut self) {
         self.sender_task = Some(tokio::spawn(Self::sender_loop(self.channel.clone(), self.value.clone(), self.period)));
     }
@@ -0,0 +1,156 @@
+use std::collections::VecDeque;
+use crate::config::{ButtonPosition, SpecialMaster};
+use crate::ma_interface::Update;
+
+//Updates are only merged with queued updates of the same key. The epoch changes with every page change or command,
+//so values are never merged across them
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum UpdateKey {
+    Fader { epoch: u64, exec_index: u8 },
+    Button { epoch: u64, exec_index: u8, position: ButtonPosition },
+    SpecialMaster { epoch: u64, master: SpecialMaster },
+}
+
+pub struct UpdateQueue {
+    queue: VecDeque<(Option<UpdateKey>, Update)>,
+    epoch: u64,
+}
+
+impl UpdateQueue {
+    pub fn new() -> Self {
+        Self {
+            queue: VecDeque::new(),
+            epoch: 0,
+        }
+    }
+
+    pub fn push(&mut self, update: Update) {
+        match &update {
+            Update::FaderUpdate(fader_value) => {
+                //Only the newest fader position matters, it takes the place of the queued one
+                let key = UpdateKey::Fader { epoch: self.epoch, exec_index: fader_value.exec_index };
+                self.replace_or_push(key, update);
+            }
+            Update::SpecialMasterUpdate(special_master_value) => {
+                //Like faders, only the newest value of a special master is sent
+                let key = UpdateKey::SpecialMaster { epoch: self.epoch, master: special_master_value.master };
+                self.replace_or_push(key, update);
+            }
+            Update::ButtonUpdate(button_value) => {
+                //Presses and releases are all kept in order, only repetitions of the same state are dropped
+                let key = Some(UpdateKey::Button { epoch: self.epoch, exec_index: button_value.exec_index, position: button_value.position });
+                let last_queued = self.queue.iter().rev().find(|(queued_key, _)| *queued_key == key);
+                if let Some((_, Update::ButtonUpdate(queued_value))) = last_queued {
+                    if queued_value.button_value == button_value.button_value {
+                        return;
+                    }
+                }
+                self.queue.push_back((key, update));
+            }
+            _ => {
+                self.epoch += 1;
+                self.queue.push_back((None, update));
+            }
+        }
+    }
+
+    fn replace_or_push(&mut self, key: UpdateKey, update: Update) {
+        if let Some(queued) = self.queue.iter_mut().find(|(queued_key, _)| *queued_key == Some(key)) {
+            queued.1 = update;
+            return;
+        }
+        self.queue.push_back((Some(key), update));
+    }
+
+    pub fn pop(&mut self) -> Option<Update> {
+        self.queue.pop_front().map(|(_, update)| update)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+impl Default for UpdateQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PageChange;
+    use crate::ma_interface::{ButtonValue, FaderValue};
+
+    fn fader(exec_index: u8, fader_value: f32) -> Update {
+        Update::FaderUpdate(FaderValue { fader_value, exec_index, range: None })
+    }
+
+    fn button(exec_index: u8, position: ButtonPosition, button_value: bool) -> Update {
+        Update::ButtonUpdate(ButtonValue { button_value, exec_index, position })
+    }
+
+    //Renders the queue so whole batches can be compared at once
+    fn drain(queue: &mut UpdateQueue) -> Vec<String> {
+        let mut updates = Vec::new();
+        while let Some(update) = queue.pop() {
+            updates.push(match update {
+                Update::FaderUpdate(value) => format!("fader {} {}", value.exec_index, value.fader_value),
+                Update::ButtonUpdate(value) => {
+                    let position = match value.position {
+                        ButtonPosition::Top => "top",
+                        ButtonPosition::Mid => "mid",
+                        ButtonPosition::Bottom => "bottom",
+                    };
+                    format!("button {} {} {}", value.exec_index, position, value.button_value)
+                }
+                Update::PageUpdate(_) => "page".to_string(),
+                _ => "other".to_string(),
+            });
+        }
+        updates
+    }
+
+    #[test]
+    fn keeps_latest_fader_value_per_executor() {
+        let mut queue = UpdateQueue::new();
+        queue.push(fader(1, 0.1));
+        queue.push(fader(2, 0.2));
+        queue.push(fader(1, 0.3));
+        assert_eq!(drain(&mut queue), ["fader 1 0.3", "fader 2 0.2"]);
+    }
+
+    #[test]
+    fn does_not_merge_fader_values_across_page_changes() {
+        let mut queue = UpdateQueue::new();
+        queue.push(fader(1, 0.1));
+        queue.push(Update::PageUpdate(PageChange::Next));
+        queue.push(fader(1, 0.2));
+        queue.push(fader(1, 0.3));
+        assert_eq!(drain(&mut queue), ["fader 1 0.1", "page", "fader 1 0.3"]);
+    }
+
+    #[test]
+    fn keeps_press_and_release_in_order() {
+        let mut queue = UpdateQueue::new();
+        queue.push(button(1, ButtonPosition::Top, true));
+        queue.push(button(1, ButtonPosition::Bottom, true));
+        queue.push(button(1, ButtonPosition::Top, false));
+        queue.push(button(1, ButtonPosition::Top, true));
+        queue.push(button(1, ButtonPosition::Top, false));
+        assert_eq!(
+            drain(&mut queue),
+            ["button 1 top true", "button 1 bottom true", "button 1 top false", "button 1 top true", "button 1 top false"]
+        );
+    }
+
+    #[test]
+    fn drops_repeated_button_states() {
+        let mut queue = UpdateQueue::new();
+        queue.push(button(1, ButtonPosition::Mid, true));
+        queue.push(button(1, ButtonPosition::Mid, true));
+        queue.push(button(2, ButtonPosition::Mid, true));
+        assert_eq!(drain(&mut queue), ["button 1 mid true", "button 2 mid true"]);
+    }
+}
@@ -0,0 +1,366 @@
+//In-process stand-in for the MA2 WebRemote, shared by the integration tests
+#![allow(dead_code)]
+
+use futures_util::{SinkExt, StreamExt};
+use midi_ma::config::ConsoleAddress;
+use midi_ma::ma_interface::endpoint::Endpoint;
+use midi_ma::LoginCredentials;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tokio_tungstenite::accept_async;
+use tokio_tungstenite::tungstenite::protocol::Message;
+
+#[derive(Clone)]
+pub struct MockExecutor {
+    pub name: String,
+    pub fader_value: f32,
+    pub button_states: [bool; 3],
+    pub running: bool,
+}
+
+impl Default for MockExecutor {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            fader_value: 0.0,
+            button_states: [false; 3],
+            running: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReceivedInput {
+    Fader { page_index: u32, exec_index: u32, value: f32 },
+    Button { page_index: u32, exec_index: u32, button_id: u32, pressed: bool },
+}
+
+#[derive(Clone, Debug)]
+pub struct LoginAttempt {
+    pub username: String,
+    pub password: String,
+    pub session: i32,
+    pub max_requests: u32,
+}
+
+pub struct MockState {
+    pub username: String,
+    pub password_hash: String,
+    pub console_page_index: u32,
+    pub executors: HashMap<(u32, u32), MockExecutor>,
+    pub logins: Vec<LoginAttempt>,
+    pub inputs: Vec<ReceivedInput>,
+    pub commands: Vec<String>,
+    pub keep_alives: usize,
+    pub logouts: Vec<i32>,
+    pub reject_commands: bool,
+    pub stale_keep_alive_on_new_session: bool,
+    next_session: i32,
+    logged_in_sessions: HashSet<i32>,
+}
+
+pub struct MockConsole {
+    address: SocketAddr,
+    state: Arc<Mutex<MockState>>,
+    clients: Arc<Mutex<Vec<UnboundedSender<Message>>>>,
+    accept_task: JoinHandle<()>,
+}
+
+impl MockConsole {
+    pub async fn start(username: &str, password: &str) -> MockConsole {
+        Self::start_at(SocketAddr::from(([127, 0, 0, 1], 0)), username, password).await
+    }
+
+    //Listens on a given address, for a console that comes back where it was unreachable before
+    pub async fn start_at(address: SocketAddr, username: &str, password: &str) -> MockConsole {
+        let listener = TcpListener::bind(address).await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let state = Arc::new(Mutex::new(MockState {
+            username: username.to_string(),
+            password_hash: format!("{:x}", md5::compute(password)),
+            console_page_index: 0,
+            executors: HashMap::new(),
+            logins: Vec::new(),
+            inputs: Vec::new(),
+            commands: Vec::new(),
+            keep_alives: 0,
+            logouts: Vec::new(),
+            reject_commands: false,
+            stale_keep_alive_on_new_session: false,
+            next_session: 1,
+            logged_in_sessions: HashSet::new(),
+        }));
+        let clients = Arc::new(Mutex::new(Vec::new()));
+        let accept_task = tokio::spawn(Self::accept_loop(listener, state.clone(), clients.clone()));
+        MockConsole {
+            address,
+            state,
+            clients,
+            accept_task,
+        }
+    }
+
+    pub fn endpoint(&self) -> Endpoint {
+        Self::endpoint_at(self.address)
+    }
+
+    pub fn endpoint_at(address: SocketAddr) -> Endpoint {
+        Endpoint::from_address(&ConsoleAddress {
+            host: address.ip().to_string(),
+            port: Some(address.port()),
+            scheme: None,
+            path: None,
+            tls: None,
+        })
+        .unwrap()
+    }
+
+    //An address nobody listens on, for a console that is switched off
+    pub fn unused_address() -> SocketAddr {
+        std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap()
+    }
+
+    pub fn login_credentials(username: &str, password: &str) -> LoginCredentials {
+        LoginCredentials {
+            username: username.to_string(),
+            password_hash: format!("{:x}", md5::compute(password)),
+        }
+    }
+
+    pub async fn state(&self) -> tokio::sync::MutexGuard<'_, MockState> {
+        self.state.lock().await
+    }
+
+    pub async fn set_executor(&self, page_index: u32, exec_index: u32, executor: MockExecutor) {
+        self.state.lock().await.executors.insert((page_index, exec_index), executor);
+    }
+
+    //Drops all sessions and tells the clients, like the console does when the WebRemote is restarted
+    pub async fn invalidate_sessions(&self) {
+        self.state.lock().await.logged_in_sessions.clear();
+        let close = json!({"realtime": false, "responseType": "close"}).to_string();
+        for client in self.clients.lock().await.iter() {
+            let _ = client.send(Message::text(close.clone()));
+        }
+    }
+
+    //Closes all connections and stops accepting new ones, like a console that is switched off
+    pub async fn stop(&mut self) {
+        self.accept_task.abort();
+        let _ = (&mut self.accept_task).await;
+        for client in self.clients.lock().await.drain(..) {
+            let _ = client.send(Message::Close(None));
+        }
+    }
+
+    //Sends a frame as is, for testing how malformed console output is handled
+    pub async fn send_raw(&self, frame: &str) {
+        for client in self.clients.lock().await.iter() {
+            let _ = client.send(Message::text(frame));
+        }
+    }
+
+    //Polls the recorded state until the condition holds, panicking after the timeout
+    pub async fn wait_for<F: Fn(&MockState) -> bool>(&self, condition: F) {
+        let result = tokio::time::timeout(Duration::from_secs(3), async {
+            loop {
+                if condition(&*self.state.lock().await) {
+                    return;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await;
+        assert!(result.is_ok(), "mock console did not reach the expected state");
+    }
+
+    async fn accept_loop(listener: TcpListener, state: Arc<Mutex<MockState>>, clients: Arc<Mutex<Vec<UnboundedSender<Message>>>>) {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(Self::client_loop(stream, state.clone(), clients.clone()));
+        }
+    }
+
+    async fn client_loop(stream: TcpStream, state: Arc<Mutex<MockState>>, clients: Arc<Mutex<Vec<UnboundedSender<Message>>>>) {
+        let Ok(websocket) = accept_async(stream).await else {
+            return;
+        };
+        let (mut socket_tx, mut socket_rx) = websocket.split();
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<Message>();
+        let writer = tokio::spawn(async move {
+            while let Some(message) = rx.recv().await {
+                if socket_tx.send(message).await.is_err() {
+                    break;
+                }
+            }
+        });
+        clients.lock().await.push(tx.clone());
+        let _ = tx.send(Message::text(json!({"status": "server ready", "appType": "gma2"}).to_string()));
+        while let Some(Ok(message)) = socket_rx.next().await {
+            let Ok(request) = serde_json::from_str::<Value>(&message.to_string()) else {
+                continue;
+            };
+            let mut state = state.lock().await;
+            if let Some(stale) = Self::stale_keep_alive(&request, &state) {
+                let _ = tx.send(Message::text(stale.to_string()));
+            }
+            let response = Self::handle_request(&request, &mut state);
+            drop(state);
+            if let Some(response) = response {
+                if tx.send(Message::text(response.to_string())).is_err() {
+                    break;
+                }
+            }
+        }
+        writer.abort();
+    }
+
+    //A keep alive reply for the dropped session can still be in flight when the client asks for a new one
+    fn stale_keep_alive(request: &Value, state: &MockState) -> Option<Value> {
+        let new_session_request = request["requestType"].is_null() && request["session"].as_i64().unwrap_or(0) == 0;
+        if !state.stale_keep_alive_on_new_session || !new_session_request || state.next_session == 1 {
+            return None;
+        }
+        Some(json!({"realtime": false, "session": state.next_session - 1, "forceLogin": true, "worldIndex": 0}))
+    }
+
+    fn handle_request(request: &Value, state: &mut MockState) -> Option<Value> {
+        let session = request["session"].as_i64().unwrap_or(0) as i32;
+        match request["requestType"].as_str() {
+            None => {
+                if session == 0 {
+                    let session = state.next_session;
+                    state.next_session += 1;
+                    //Like the console, a new session has to log in before it can be used
+                    Some(json!({"realtime": false, "session": session, "forceLogin": true, "worldIndex": 0}))
+                } else {
+                    state.keep_alives += 1;
+                    let force_login = !state.logged_in_sessions.contains(&session);
+                    Some(json!({"realtime": false, "session": session, "forceLogin": force_login, "worldIndex": 0}))
+                }
+            }
+            Some("login") => {
+                let username = request["username"].as_str().unwrap_or_default().to_string();
+                let password = request["password"].as_str().unwrap_or_default().to_string();
+                let result = username == state.username && password == state.password_hash;
+                state.logins.push(LoginAttempt {
+                    username,
+                    password,
+                    session,
+                    max_requests: request["maxRequests"].as_u64().unwrap_or(0) as u32,
+                });
+                if result {
+                    state.logged_in_sessions.insert(session);
+                }
+                Some(json!({"realtime": false, "responseType": "login", "result": result, "worldIndex": 0}))
+            }
+            Some("command") => {
+                if !state.logged_in_sessions.contains(&session) {
+                    return None;
+                }
+                let command = request["command"].as_str().unwrap_or_default().to_string();
+                state.commands.push(command);
+                let prompt = if state.reject_commands { "Error #1: UNKNOWN COMMAND" } else { "" };
+                Some(json!({"realtime": false, "responseType": "command", "prompt": prompt, "promptcolor": "#FFFFFF", "worldIndex": 0}))
+            }
+            Some("playbacks") => {
+                if !state.logged_in_sessions.contains(&session) {
+                    return None;
+                }
+                Some(Self::playbacks_response(request, state))
+            }
+            Some("playbacks_userInput") => {
+                if state.logged_in_sessions.contains(&session) {
+                    Self::receive_user_input(request, state);
+                }
+                None
+            }
+            Some("close") => {
+                state.logged_in_sessions.remove(&session);
+                state.logouts.push(session);
+                Some(json!({"realtime": false, "responseType": "close", "worldIndex": 0}))
+            }
+            Some(_) => None,
+        }
+    }
+
+    fn receive_user_input(request: &Value, state: &mut MockState) {
+        let page_index = request["pageIndex"].as_u64().unwrap_or(0) as u32;
+        let exec_index = request["execIndex"].as_u64().unwrap_or(0) as u32;
+        let executor = state.executors.entry((page_index, exec_index)).or_default();
+        if let Some(value) = request["faderValue"].as_f64() {
+            executor.fader_value = value as f32;
+            state.inputs.push(ReceivedInput::Fader {
+                page_index,
+                exec_index,
+                value: value as f32,
+            });
+        } else {
+            let button_id = request["buttonId"].as_u64().unwrap_or(0) as u32;
+            let pressed = request["pressed"].as_bool().unwrap_or(false);
+            if let Some(button_state) = executor.button_states.get_mut(button_id as usize) {
+                *button_state = pressed;
+            }
+            state.inputs.push(ReceivedInput::Button {
+                page_index,
+                exec_index,
+                button_id,
+                pressed,
+            });
+        }
+    }
+
+    fn playbacks_response(request: &Value, state: &MockState) -> Value {
+        let page_index = request["pageIndex"].as_u64().unwrap_or(0) as u32;
+        let start_index = request["startIndex"][0].as_u64().unwrap_or(0) as u32;
+        let items_count = request["itemsCount"][0].as_u64().unwrap_or(0) as u32;
+        let default_executor = MockExecutor::default();
+        let executors: Vec<Value> = (start_index..start_index + items_count)
+            .map(|exec_index| Self::executor_json(exec_index, state.executors.get(&(page_index, exec_index)).unwrap_or(&default_executor)))
+            .collect();
+        let items: Vec<Vec<Value>> = executors.chunks(5).map(|chunk| chunk.to_vec()).collect();
+        //Like the console, iPage reports the page shown on the console, not the requested one
+        json!({
+            "realtime": false,
+            "responseType": "playbacks",
+            "responseSubType": 2,
+            "iPage": state.console_page_index,
+            "itemGroups": [{"itemsType": 2, "cntPages": 10000, "items": items}],
+            "worldIndex": 0,
+        })
+    }
+
+    fn executor_json(exec_index: u32, executor: &MockExecutor) -> Value {
+        let button = |id: usize, label: &str| json!({"id": id, "t": label, "s": executor.button_states[id], "c": "#C0C0C0"});
+        json!({
+            "i": {"t": format!("{}", exec_index + 1), "c": "#C0C0C0"},
+            "oType": {"t": "Exec"},
+            "oI": {"t": ""},
+            "tt": {"t": executor.name},
+            "bC": "#3F3F3F",
+            "bdC": "#3F3F3F",
+            "cues": {},
+            "combinedItems": 1,
+            "iExec": exec_index,
+            "isRun": if executor.running { 1 } else { 0 },
+            "executorBlocks": [{
+                "button1": button(0, "Go"),
+                "button2": button(1, "Pause"),
+                "button3": button(2, "Flash"),
+                "fader": {"bdC": "#3F3F3F", "tt": "Master", "v": executor.fader_value, "vT": format!("{:.0}%", executor.fader_value * 100.0), "min": 0.0, "max": 1.0},
+            }],
+        })
+    }
+}
+
+impl Drop for MockConsole {
+    fn drop(&mut self) {
+        self.accept_task.abort();
+    }
+}
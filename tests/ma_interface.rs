@@ -0,0 +1,300 @@
+mod common;
+
+use common::{MockConsole, MockExecutor, ReceivedInput};
+use midi_ma::config::{ButtonPosition, SpecialMaster};
+use midi_ma::ma_interface::error::MaError;
+use midi_ma::ma_interface::playbacks_diff::ExecutorEvent;
+use midi_ma::ma_interface::recording::{read_capture, Capture, FrameDirection, FrameKind};
+use midi_ma::ma_interface::{ButtonValue, FaderValue, MaInterface, SpecialMasterValue, Update};
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::frame::CloseFrame;
+use tokio_tungstenite::tungstenite::protocol::Message;
+
+async fn connect(console: &MockConsole) -> MaInterface {
+    MaInterface::new(&console.endpoint(), &MockConsole::login_credentials("remote", "remote"), 10).await.unwrap()
+}
+
+#[tokio::test]
+async fn logs_in_with_session_and_max_requests() {
+    let console = MockConsole::start("remote", "remote").await;
+    let _ma = MaInterface::new(&console.endpoint(), &MockConsole::login_credentials("remote", "remote"), 7).await.unwrap();
+    let state = console.state().await;
+    assert_eq!(state.logins.len(), 1);
+    assert_eq!(state.logins[0].username, "remote");
+    assert_eq!(state.logins[0].session, 1);
+    assert_eq!(state.logins[0].max_requests, 7);
+}
+
+#[tokio::test]
+async fn rejects_wrong_credentials() {
+    let console = MockConsole::start("remote", "remote").await;
+    let result = MaInterface::new(&console.endpoint(), &MockConsole::login_credentials("remote", "wrong"), 10).await;
+    assert!(matches!(result, Err(MaError::AuthRejected)));
+}
+
+#[tokio::test]
+async fn polls_scripted_executor_state() {
+    let console = MockConsole::start("remote", "remote").await;
+    console.set_executor(0, 3, MockExecutor {
+        name: String::from("Front"),
+        fader_value: 0.5,
+        button_states: [true, false, false],
+        running: true,
+    }).await;
+    let mut ma = connect(&console).await;
+    ma.set_executor_ranges(&[midi_ma::config::ExecutorRange { start_index: 0, items_count: 20 }]);
+
+    let records = ma.poll_executors().await.unwrap();
+    assert_eq!(records.len(), 20);
+    let record = records.iter().find(|record| record.exec_index == 3).unwrap();
+    assert_eq!(record.fader_value, 0.5);
+    assert!(record.button_state(ButtonPosition::Bottom));
+    assert!(!record.button_state(ButtonPosition::Top));
+    assert_eq!(record.state.name, "Front");
+    assert!(record.state.running);
+}
+
+#[tokio::test]
+async fn keys_records_by_requested_page() {
+    let console = MockConsole::start("remote", "remote").await;
+    console.set_executor(1, 0, MockExecutor { name: String::from("Back"), ..Default::default() }).await;
+    console.state().await.console_page_index = 4;
+    let mut ma = connect(&console).await;
+    ma.set_page_index(1);
+    ma.set_executor_ranges(&[midi_ma::config::ExecutorRange { start_index: 0, items_count: 5 }]);
+
+    let records = ma.poll_executors().await.unwrap();
+
+    assert!(records.iter().all(|record| record.page_index == 1));
+    let executor_states = ma.executor_states();
+    assert_eq!(executor_states.get(1, 0).await.unwrap().name, "Back");
+    assert!(executor_states.get(4, 0).await.is_none());
+}
+
+#[tokio::test]
+async fn reports_only_changed_executors() {
+    let console = MockConsole::start("remote", "remote").await;
+    let mut ma = connect(&console).await;
+    ma.set_executor_ranges(&[midi_ma::config::ExecutorRange { start_index: 0, items_count: 5 }]);
+
+    assert!(!ma.poll_changes().await.unwrap().is_empty());
+    assert!(ma.poll_changes().await.unwrap().is_empty());
+
+    console.set_executor(0, 2, MockExecutor { fader_value: 0.25, ..Default::default() }).await;
+    let events = ma.poll_changes().await.unwrap();
+    let fader_changes: Vec<(u32, f32)> = events
+        .iter()
+        .filter_map(|event| match event {
+            ExecutorEvent::FaderChanged { exec_index, value, .. } => Some((*exec_index, *value)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(fader_changes, vec![(2, 0.25)]);
+}
+
+#[tokio::test]
+async fn forwards_fader_and_button_input() {
+    let console = MockConsole::start("remote", "remote").await;
+    let mut ma = connect(&console).await;
+    ma.set_page_index(1);
+
+    ma.send_update(Update::FaderUpdate(FaderValue { fader_value: 0.75, exec_index: 4, range: None })).await.unwrap();
+    ma.send_update(Update::ButtonUpdate(ButtonValue { button_value: true, exec_index: 4, position: ButtonPosition::Top })).await.unwrap();
+    ma.send_update(Update::ButtonUpdate(ButtonValue { button_value: false, exec_index: 4, position: ButtonPosition::Top })).await.unwrap();
+
+    console.wait_for(|state| state.inputs.len() == 3).await;
+    assert_eq!(console.state().await.inputs, vec![
+        ReceivedInput::Fader { page_index: 1, exec_index: 4, value: 0.75 },
+        ReceivedInput::Button { page_index: 1, exec_index: 4, button_id: 2, pressed: true },
+        ReceivedInput::Button { page_index: 1, exec_index: 4, button_id: 2, pressed: false },
+    ]);
+}
+
+#[tokio::test]
+async fn logs_in_again_after_session_loss() {
+    let console = MockConsole::start("remote", "remote").await;
+    let mut ma = connect(&console).await;
+    ma.poll_executors().await.unwrap();
+
+    console.invalidate_sessions().await;
+    //Give the close message time to arrive before the next poll
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    ma.poll_executors().await.unwrap();
+
+    let state = console.state().await;
+    assert_eq!(state.logins.len(), 2);
+    assert_eq!(state.logins[1].session, 2);
+}
+
+#[tokio::test]
+async fn ignores_stale_keep_alive_replies_while_logging_in_again() {
+    let console = MockConsole::start("remote", "remote").await;
+    let mut ma = connect(&console).await;
+    ma.poll_executors().await.unwrap();
+
+    console.state().await.stale_keep_alive_on_new_session = true;
+    console.invalidate_sessions().await;
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    ma.poll_executors().await.unwrap();
+
+    let state = console.state().await;
+    assert_eq!(state.logins.len(), 2);
+    assert_eq!(state.logins[1].session, 2);
+}
+
+#[tokio::test]
+async fn ignores_keep_alive_replies_for_the_replaced_session() {
+    let console = MockConsole::start("remote", "remote").await;
+    let mut ma = connect(&console).await;
+    ma.poll_executors().await.unwrap();
+    console.invalidate_sessions().await;
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    ma.poll_executors().await.unwrap();
+
+    console.send_raw(r#"{"realtime":false,"session":1,"forceLogin":true,"worldIndex":0}"#).await;
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    ma.poll_executors().await.unwrap();
+
+    assert_eq!(console.state().await.logins.len(), 2);
+}
+
+#[tokio::test]
+async fn logs_in_again_when_keep_alive_demands_it() {
+    let console = MockConsole::start("remote", "remote").await;
+    let mut ma = connect(&console).await;
+    ma.poll_executors().await.unwrap();
+
+    console.send_raw(r#"{"realtime":false,"session":1,"forceLogin":true,"worldIndex":0}"#).await;
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    ma.poll_executors().await.unwrap();
+
+    let state = console.state().await;
+    assert_eq!(state.logins.len(), 2);
+    assert_eq!(state.logins[1].session, 2);
+}
+
+#[tokio::test]
+async fn logs_out_on_close() {
+    let console = MockConsole::start("remote", "remote").await;
+    let mut ma = connect(&console).await;
+    ma.send_update(Update::FaderUpdate(FaderValue { fader_value: 0.5, exec_index: 1, range: None })).await.unwrap();
+
+    ma.close().await.unwrap();
+
+    let state = console.state().await;
+    assert_eq!(state.logouts, vec![1]);
+    //Requests queued before the logout still reach the console
+    assert_eq!(state.inputs, vec![ReceivedInput::Fader { page_index: 0, exec_index: 1, value: 0.5 }]);
+}
+
+#[tokio::test]
+async fn sends_command_updates_without_waiting_for_the_prompt() {
+    let console = MockConsole::start("remote", "remote").await;
+    let mut ma = connect(&console).await;
+    console.state().await.reject_commands = true;
+
+    let result = tokio::time::timeout(std::time::Duration::from_millis(100), ma.send_update(Update::CommandUpdate(String::from("Nonsense")))).await;
+
+    assert!(matches!(result, Ok(Ok(()))));
+    console.wait_for(|state| state.commands == vec!["Nonsense"]).await;
+    ma.poll_executors().await.unwrap();
+}
+
+#[tokio::test]
+async fn sends_special_master_values_as_commands() {
+    let console = MockConsole::start("remote", "remote").await;
+    let mut ma = connect(&console).await;
+
+    ma.send_update(Update::SpecialMasterUpdate(SpecialMasterValue { master: SpecialMaster::SpeedMaster(1), value: 120.0 })).await.unwrap();
+
+    console.wait_for(|state| state.commands == vec!["SpecialMaster 3.1 At 120.0"]).await;
+}
+
+#[tokio::test]
+async fn reports_rejected_commands() {
+    let console = MockConsole::start("remote", "remote").await;
+    let mut ma = connect(&console).await;
+
+    ma.send_command("Go Executor 1").await.unwrap();
+    console.state().await.reject_commands = true;
+    let result = ma.send_command("Nonsense").await;
+
+    assert!(matches!(result, Err(MaError::CommandRejected(_))));
+    assert_eq!(console.state().await.commands, vec!["Go Executor 1", "Nonsense"]);
+}
+
+#[tokio::test]
+async fn replays_captured_session() {
+    let capture_file = std::env::temp_dir().join(format!("midi_ma_capture_{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&capture_file);
+    let console = MockConsole::start("remote", "remote").await;
+    console.set_executor(0, 0, MockExecutor { fader_value: 0.4, ..Default::default() }).await;
+    let mut endpoint = console.endpoint();
+    endpoint.capture_file = Some(capture_file.clone());
+    let mut ma = MaInterface::new(&endpoint, &MockConsole::login_credentials("remote", "remote"), 10).await.unwrap();
+    ma.set_executor_ranges(&[midi_ma::config::ExecutorRange { start_index: 0, items_count: 1 }]);
+    ma.poll_executors().await.unwrap();
+    drop(ma);
+    drop(console);
+    //The capture is written in the background
+    let captured = tokio::time::timeout(std::time::Duration::from_secs(3), async {
+        while !std::fs::read_to_string(&capture_file).unwrap_or_default().contains("itemGroups") {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+    }).await;
+    assert!(captured.is_ok());
+
+    endpoint.capture_file = None;
+    endpoint.replay_file = Some(capture_file.clone());
+    let mut ma = MaInterface::new(&endpoint, &MockConsole::login_credentials("remote", "remote"), 10).await.unwrap();
+    ma.set_executor_ranges(&[midi_ma::config::ExecutorRange { start_index: 0, items_count: 1 }]);
+    let records = ma.poll_executors().await.unwrap();
+    let _ = std::fs::remove_file(&capture_file);
+
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].fader_value, 0.4);
+}
+
+#[tokio::test]
+async fn captures_binary_and_control_frames() {
+    let capture_file = std::env::temp_dir().join(format!("midi_ma_control_capture_{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&capture_file);
+    let capture = Capture::open(&capture_file).await.unwrap();
+    capture.record(FrameDirection::Received, &Message::text("{}"));
+    capture.record(FrameDirection::Received, &Message::binary(b"{\"realtime\":true}".to_vec()));
+    capture.record(FrameDirection::Received, &Message::Ping(vec![1, 2, 3]));
+    capture.record(FrameDirection::Sent, &Message::Pong(vec![1, 2, 3]));
+    capture.record(FrameDirection::Received, &Message::Close(Some(CloseFrame { code: CloseCode::Away, reason: "restart".into() })));
+    let frames = tokio::time::timeout(std::time::Duration::from_secs(3), async {
+        loop {
+            if let Ok(frames) = read_capture(&capture_file).await {
+                if frames.len() == 5 {
+                    return frames;
+                }
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+    }).await.unwrap();
+    let _ = std::fs::remove_file(&capture_file);
+
+    let kinds: Vec<(FrameKind, usize)> = frames.iter().map(|frame| (frame.kind, frame.length)).collect();
+    assert!(kinds == [(FrameKind::Text, 2), (FrameKind::Binary, 17), (FrameKind::Ping, 3), (FrameKind::Pong, 3), (FrameKind::Close, 7)]);
+    assert_eq!(frames[1].frame, "{\"realtime\":true}");
+    assert_eq!(frames[4].frame, "restart");
+}
+
+#[tokio::test]
+async fn tolerates_and_counts_malformed_frames() {
+    let console = MockConsole::start("remote", "remote").await;
+    let mut ma = connect(&console).await;
+    ma.set_executor_ranges(&[midi_ma::config::ExecutorRange { start_index: 0, items_count: 1 }]);
+
+    console.send_raw(r#"{"realtime":false,"session":1,"worldIndex":0,"worldIndex":0}"#).await;
+    console.send_raw("this is not json").await;
+    console.send_raw(r#"{"realtime":false,"responseType":"playbacks","iPage":"broken"}"#).await;
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+    assert_eq!(ma.poll_executors().await.unwrap().len(), 1);
+    assert_eq!(ma.undecodable_frames().await, 2);
+    assert_eq!(console.state().await.logins.len(), 1);
+}
@@ -0,0 +1,359 @@
+mod common;
+
+use common::{MockConsole, MockExecutor, ReceivedInput};
+use midi_ma::bridge::main_loop;
+use midi_ma::config::{ButtonConfig, ButtonMidiConfig, ButtonPosition, Config, DeviceModelConfig, ExecutorRange, FailoverConfig, MotorFaderConfig, PageButtonConfig, PageChange, ReconnectConfig, SpecialMaster, SpecialMasterConfig, StatusIndication, StatusIndicatorConfig};
+use midi_ma::ma_interface::endpoint::Endpoint;
+use midi_ma::ma_interface::{ButtonValue, FaderRange, MaInterface};
+use midi_ma::midi_controller::midi_device::feedback_handle::ModelFeedbackHandle;
+use midi_ma::midi_controller::midi_device::model::components::MidiMessageReceiver;
+use midi_ma::midi_controller::midi_device::model::DeviceModel;
+use midi_ma::midi_controller::midi_device::MidiDevice;
+use midi_ma::midi_controller::midi_message::MidiMessage;
+use midi_ma::midi_controller::MidiController;
+use midi_ma::mirror::MirrorConsole;
+use midi_ma::{FaderValue, Update};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+use tokio::task::JoinHandle;
+
+fn device_model_config() -> DeviceModelConfig {
+    DeviceModelConfig {
+        motor_faders: vec![MotorFaderConfig {
+            input_midi_byte_0: 0xB0,
+            input_midi_byte_1: 0x07,
+            output_midi_byte_0: 0xB0,
+            output_midi_byte_1: 0x07,
+            min_value: None,
+            max_value: None,
+            input_feedback: None,
+            ma_executor_index: 0,
+        }],
+        rotary_encoders: Vec::new(),
+        buttons: vec![ButtonConfig {
+            midi: ButtonMidiConfig {
+                input_midi_byte_0: 0x90,
+                input_midi_byte_1: 0x10,
+                output_midi_byte_0: 0x90,
+                output_midi_byte_1: 0x10,
+                low_value: None,
+                high_value: None,
+            },
+            input_feedback: None,
+            ma_executor_index: 0,
+            position: ButtonPosition::Bottom,
+        }],
+        page_buttons: vec![PageButtonConfig {
+            midi: ButtonMidiConfig {
+                input_midi_byte_0: 0x90,
+                input_midi_byte_1: 0x20,
+                output_midi_byte_0: 0x90,
+                output_midi_byte_1: 0x20,
+                low_value: None,
+                high_value: None,
+            },
+            action: PageChange::Goto(1),
+        }],
+        command_buttons: Vec::new(),
+        special_masters: Vec::new(),
+        status_indicator: None,
+        goodbye: None,
+    }
+}
+
+fn create_device_model() -> (DeviceModel, UnboundedReceiver<Update>, UnboundedReceiver<MidiMessage>) {
+    let (ma_sender, ma_receiver) = tokio::sync::mpsc::unbounded_channel();
+    let (midi_sender, midi_receiver) = tokio::sync::mpsc::unbounded_channel();
+    let model = DeviceModel::new(device_model_config(), ModelFeedbackHandle::new(ma_sender, midi_sender)).unwrap();
+    (model, ma_receiver, midi_receiver)
+}
+
+fn bridge_config() -> Config {
+    Config {
+        ma_poll_interval: 10,
+        ma_executor_ranges: Some(vec![ExecutorRange { start_index: 0, items_count: 1 }]),
+        reconnect: Some(ReconnectConfig {
+            initial_delay: Some(50),
+            max_delay: Some(200),
+            max_attempts: None,
+            jitter: Some(0.0),
+        }),
+        ..Config::default()
+    }
+}
+
+//A device driven through channels instead of midi ports, connected to the consoles by the main loop of the binary
+struct Surface {
+    midi_input: UnboundedSender<MidiMessage>,
+    midi_output: UnboundedReceiver<MidiMessage>,
+    shutdown_sender: UnboundedSender<()>,
+    main_loop_task: JoinHandle<Result<(), String>>,
+}
+
+impl Surface {
+    fn start(config: Config, model_config: DeviceModelConfig, endpoints: Vec<Endpoint>) -> Surface {
+        let (ma_sender, update_receiver) = tokio::sync::mpsc::unbounded_channel();
+        let (midi_sender, midi_output) = tokio::sync::mpsc::unbounded_channel();
+        let (midi_input, midi_input_receiver) = tokio::sync::mpsc::unbounded_channel();
+        let model = DeviceModel::new(model_config, ModelFeedbackHandle::new(ma_sender, midi_sender)).unwrap();
+        let midi_controller = MidiController::from_devices(vec![MidiDevice::from_model(model, midi_input_receiver)]);
+        let (shutdown_sender, shutdown_receiver) = tokio::sync::mpsc::unbounded_channel();
+        let main_loop_task = tokio::spawn(async move {
+            main_loop(Arc::new(config), endpoints, MockConsole::login_credentials("remote", "remote"), midi_controller, update_receiver, &[], shutdown_receiver)
+                .await
+                .map_err(|e| e.to_string())
+        });
+        Surface {
+            midi_input,
+            midi_output,
+            shutdown_sender,
+            main_loop_task,
+        }
+    }
+
+    fn press(&self, data: [u8; 3]) {
+        self.midi_input.send(MidiMessage { data }).unwrap();
+    }
+
+    //Reads the midi output until the message shows up, returning everything read before it
+    async fn wait_for_midi(&mut self, data: [u8; 3]) -> Vec<[u8; 3]> {
+        let mut received = Vec::new();
+        let result = tokio::time::timeout(Duration::from_secs(3), async {
+            while let Some(message) = self.midi_output.recv().await {
+                if message.data == data {
+                    return;
+                }
+                received.push(message.data);
+            }
+        })
+        .await;
+        assert!(result.is_ok(), "midi device did not receive {:?}", data);
+        received
+    }
+
+    async fn shutdown(self) {
+        self.shutdown_sender.send(()).unwrap();
+        let result = tokio::time::timeout(Duration::from_secs(3), self.main_loop_task).await.unwrap().unwrap();
+        assert_eq!(result, Ok(()));
+    }
+}
+
+#[tokio::test]
+async fn midi_input_reaches_console() {
+    let console = MockConsole::start("remote", "remote").await;
+    let surface = Surface::start(bridge_config(), device_model_config(), vec![console.endpoint()]);
+    console.wait_for(|state| state.logins.len() == 1).await;
+
+    surface.press([0xB0, 0x07, 127]);
+    surface.press([0x90, 0x10, 127]);
+
+    console.wait_for(|state| state.inputs.len() == 2).await;
+    //Faders are sent through their periodic sender, so their order relative to buttons isn't fixed
+    let inputs = console.state().await.inputs.clone();
+    assert!(inputs.contains(&ReceivedInput::Fader { page_index: 0, exec_index: 0, value: 1.0 }));
+    assert!(inputs.contains(&ReceivedInput::Button { page_index: 0, exec_index: 0, button_id: 0, pressed: true }));
+    surface.shutdown().await;
+}
+
+#[tokio::test]
+async fn degenerate_fader_range_moves_fader_to_minimum() {
+    let (mut model, _ma_receiver, mut midi_receiver) = create_device_model();
+
+    model.receive_update_from_ma(Update::FaderUpdate(FaderValue { fader_value: 5.0, exec_index: 0, range: Some(FaderRange { min: 5.0, max: 5.0 }) })).await.unwrap();
+
+    let message = tokio::time::timeout(Duration::from_secs(1), midi_receiver.recv()).await.unwrap().unwrap();
+    assert_eq!(message.data, [0xB0, 0x07, 0]);
+}
+
+#[tokio::test]
+async fn degenerate_special_master_range_sends_master_minimum() {
+    let mut config = device_model_config();
+    config.special_masters = vec![SpecialMasterConfig {
+        input_midi_byte_0: 0xB0,
+        input_midi_byte_1: 0x30,
+        output_midi_byte_0: 0xB0,
+        output_midi_byte_1: 0x30,
+        min_value: Some(64),
+        max_value: Some(64),
+        input_mode: None,
+        relative_step: None,
+        master: SpecialMaster::SpeedMaster(1),
+        master_min_value: Some(50.0),
+        master_max_value: Some(50.0),
+    }];
+    let (ma_sender, mut ma_receiver) = tokio::sync::mpsc::unbounded_channel();
+    let (midi_sender, mut midi_receiver) = tokio::sync::mpsc::unbounded_channel();
+    let mut model = DeviceModel::new(config, ModelFeedbackHandle::new(ma_sender, midi_sender)).unwrap();
+
+    model.receive_midi_message(MidiMessage { data: [0xB0, 0x30, 100] }).await.unwrap();
+
+    let update = tokio::time::timeout(Duration::from_secs(1), ma_receiver.recv()).await.unwrap().unwrap();
+    let Update::SpecialMasterUpdate(special_master_value) = update else {
+        panic!("expected a special master update");
+    };
+    assert_eq!(special_master_value.value, 50.0);
+    let message = tokio::time::timeout(Duration::from_secs(1), midi_receiver.recv()).await.unwrap().unwrap();
+    assert_eq!(message.data, [0xB0, 0x30, 64]);
+}
+
+#[tokio::test]
+async fn console_changes_reach_midi_device() {
+    let console = MockConsole::start("remote", "remote").await;
+    console.set_executor(0, 0, MockExecutor { fader_value: 0.5, button_states: [true, false, false], ..Default::default() }).await;
+    let mut ma = MaInterface::new(&console.endpoint(), &MockConsole::login_credentials("remote", "remote"), 10).await.unwrap();
+    ma.set_executor_ranges(&[ExecutorRange { start_index: 0, items_count: 1 }]);
+    let (mut model, _ma_receiver, mut midi_receiver) = create_device_model();
+
+    for event in ma.poll_changes().await.unwrap() {
+        if let Some(update) = event.to_update() {
+            model.receive_update_from_ma(update).await.unwrap();
+        }
+    }
+
+    let mut messages = Vec::new();
+    while messages.len() < 2 {
+        let message = tokio::time::timeout(Duration::from_secs(1), midi_receiver.recv()).await.unwrap().unwrap();
+        messages.push(message.data);
+    }
+    assert!(messages.contains(&[0xB0, 0x07, 64]));
+    assert!(messages.contains(&[0x90, 0x10, 127]));
+}
+
+#[tokio::test]
+async fn page_switch_from_midi_restores_button_leds() {
+    let console = MockConsole::start("remote", "remote").await;
+    console.set_executor(0, 0, MockExecutor { button_states: [true, false, false], ..Default::default() }).await;
+    console.set_executor(1, 0, MockExecutor { button_states: [true, false, false], ..Default::default() }).await;
+    let mut surface = Surface::start(bridge_config(), device_model_config(), vec![console.endpoint()]);
+    surface.wait_for_midi([0x90, 0x10, 127]).await;
+
+    surface.press([0x90, 0x20, 127]);
+    //The Goto page button lights up once the console switched to its page
+    surface.wait_for_midi([0x90, 0x20, 127]).await;
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let mut button_led = None;
+    while let Ok(message) = surface.midi_output.try_recv() {
+        if message.data[..2] == [0x90, 0x10] {
+            button_led = Some(message.data[2]);
+        }
+    }
+    assert_eq!(button_led, Some(127));
+    surface.shutdown().await;
+}
+
+#[tokio::test]
+async fn fails_over_to_backup_console_and_resyncs_the_surface() {
+    let mut primary = MockConsole::start("remote", "remote").await;
+    let backup = MockConsole::start("remote", "remote").await;
+    primary.set_executor(0, 0, MockExecutor { fader_value: 0.5, ..Default::default() }).await;
+    backup.set_executor(0, 0, MockExecutor { fader_value: 0.5, ..Default::default() }).await;
+    let mut surface = Surface::start(bridge_config(), device_model_config(), vec![primary.endpoint(), backup.endpoint()]);
+    surface.wait_for_midi([0xB0, 0x07, 64]).await;
+
+    primary.stop().await;
+
+    backup.wait_for(|state| state.logins.len() == 1).await;
+    //The backup reports the same fader position, the surface gets it anyway to catch up with anything missed
+    surface.wait_for_midi([0xB0, 0x07, 64]).await;
+    surface.shutdown().await;
+    assert_eq!(backup.state().await.logouts, vec![1]);
+}
+
+#[tokio::test]
+async fn fails_back_to_primary_console_once_it_is_reachable() {
+    let primary_address = MockConsole::unused_address();
+    let backup = MockConsole::start("remote", "remote").await;
+    backup.set_executor(0, 0, MockExecutor { fader_value: 0.5, ..Default::default() }).await;
+    let config = Config {
+        failover: Some(FailoverConfig {
+            failback: Some(true),
+            failback_check_interval: Some(100),
+        }),
+        ..bridge_config()
+    };
+    let mut surface = Surface::start(config, device_model_config(), vec![MockConsole::endpoint_at(primary_address), backup.endpoint()]);
+    surface.wait_for_midi([0xB0, 0x07, 64]).await;
+
+    let primary = MockConsole::start_at(primary_address, "remote", "remote").await;
+    primary.set_executor(0, 0, MockExecutor { fader_value: 1.0, ..Default::default() }).await;
+
+    surface.wait_for_midi([0xB0, 0x07, 127]).await;
+    assert_eq!(primary.state().await.logins.len(), 1);
+    surface.shutdown().await;
+    assert_eq!(primary.state().await.logouts, vec![1]);
+}
+
+#[tokio::test]
+async fn indicates_lost_connection_until_the_console_is_back() {
+    let address = MockConsole::unused_address();
+    let mut console = MockConsole::start_at(address, "remote", "remote").await;
+    console.set_executor(0, 0, MockExecutor { button_states: [true, false, false], ..Default::default() }).await;
+    let mut model_config = device_model_config();
+    model_config.status_indicator = Some(StatusIndicatorConfig {
+        indication: StatusIndication::DimLeds { dim_value: 5 },
+        indicate_degraded: None,
+    });
+    let mut surface = Surface::start(bridge_config(), model_config, vec![MockConsole::endpoint_at(address)]);
+    surface.wait_for_midi([0x90, 0x10, 127]).await;
+
+    console.stop().await;
+    surface.wait_for_midi([0x90, 0x10, 5]).await;
+
+    let console = MockConsole::start_at(address, "remote", "remote").await;
+    console.set_executor(0, 0, MockExecutor { button_states: [true, false, false], ..Default::default() }).await;
+    surface.wait_for_midi([0x90, 0x10, 127]).await;
+    surface.shutdown().await;
+}
+
+#[tokio::test]
+async fn sends_pending_updates_and_logs_out_on_shutdown() {
+    let console = MockConsole::start("remote", "remote").await;
+    let surface = Surface::start(bridge_config(), device_model_config(), vec![console.endpoint()]);
+    console.wait_for(|state| state.logins.len() == 1).await;
+
+    surface.press([0xB0, 0x07, 127]);
+    surface.shutdown().await;
+
+    let state = console.state().await;
+    assert_eq!(state.inputs, vec![ReceivedInput::Fader { page_index: 0, exec_index: 0, value: 1.0 }]);
+    assert_eq!(state.logouts, vec![1]);
+}
+
+#[tokio::test]
+async fn mirror_console_receives_updates() {
+    let console = MockConsole::start("remote", "remote").await;
+    let mut mirror = MirrorConsole::start(String::from("mirror"), console.endpoint(), MockConsole::login_credentials("remote", "remote"), 10, ReconnectConfig::default());
+    console.wait_for(|state| state.logins.len() == 1).await;
+    //Lets the mirror finish connecting, so the updates below are sent live
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    mirror.sender().send(Update::PageUpdate(PageChange::Goto(2))).unwrap();
+    mirror.sender().send(Update::FaderUpdate(FaderValue { fader_value: 0.3, exec_index: 5, range: None })).unwrap();
+
+    console.wait_for(|state| !state.inputs.is_empty()).await;
+    assert_eq!(console.state().await.inputs, vec![ReceivedInput::Fader { page_index: 2, exec_index: 5, value: 0.3 }]);
+
+    mirror.shutdown().await;
+    assert_eq!(console.state().await.logouts, vec![1]);
+}
+
+#[tokio::test]
+async fn mirror_console_catches_up_with_values_queued_while_connecting() {
+    let console = MockConsole::start("remote", "remote").await;
+    let mut mirror = MirrorConsole::start(String::from("mirror"), console.endpoint(), MockConsole::login_credentials("remote", "remote"), 10, ReconnectConfig::default());
+    mirror.sender().send(Update::PageUpdate(PageChange::Goto(3))).unwrap();
+    mirror.sender().send(Update::FaderUpdate(FaderValue { fader_value: 0.1, exec_index: 5, range: None })).unwrap();
+    mirror.sender().send(Update::ButtonUpdate(ButtonValue { button_value: true, exec_index: 5, position: ButtonPosition::Top })).unwrap();
+    mirror.sender().send(Update::CommandUpdate(String::from("Go Exec 1.5"))).unwrap();
+    mirror.sender().send(Update::FaderUpdate(FaderValue { fader_value: 0.2, exec_index: 5, range: None })).unwrap();
+
+    console.wait_for(|state| !state.inputs.is_empty()).await;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let state = console.state().await;
+    assert_eq!(state.inputs, vec![ReceivedInput::Fader { page_index: 3, exec_index: 5, value: 0.2 }]);
+    assert!(state.commands.is_empty());
+    drop(state);
+    mirror.shutdown().await;
+}